@@ -0,0 +1,2386 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::*;
+use serde::{Serialize, Deserialize};
+use chrono::{NaiveDate, Datelike};
+use rand::{SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, Normal};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LoanType {
+    Annuity,
+    BuildingSavings,
+    /// Constant principal repayment (Ratendarlehen).
+    Linear,
+    /// Interest-only loan repaid at maturity (endfälliges Darlehen).
+    Bullet,
+}
+
+impl Default for LoanType {
+    fn default() -> Self {
+        LoanType::Annuity
+    }
+}
+
+/// Which of the annuity inputs is given: the initial clearance rate, leaving the
+/// remaining debt open, or the runtime, solving the payment so the loan is paid off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AnnuityMode {
+    ClearanceRate,
+    Runtime,
+}
+
+impl Default for AnnuityMode {
+    fn default() -> Self {
+        AnnuityMode::ClearanceRate
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PaymentFrequency {
+    Monthly,
+    Quarterly,
+    SemiAnnual,
+    Annual,
+}
+
+impl Default for PaymentFrequency {
+    fn default() -> Self {
+        PaymentFrequency::Monthly
+    }
+}
+
+impl PaymentFrequency {
+    /// Length of a payment period in months.
+    pub fn months(&self) -> i32 {
+        match self {
+            PaymentFrequency::Monthly => 1,
+            PaymentFrequency::Quarterly => 3,
+            PaymentFrequency::SemiAnnual => 6,
+            PaymentFrequency::Annual => 12,
+        }
+    }
+
+    pub fn per_year(&self) -> Decimal {
+        Decimal::from(12 / self.months())
+    }
+}
+
+/// Day count convention used to derive the interest of a period from the annual rate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DayCount {
+    Thirty360,
+    Act360,
+    Act365,
+}
+
+impl Default for DayCount {
+    fn default() -> Self {
+        DayCount::Thirty360
+    }
+}
+
+/// Whether interest is charged at the end of a period on the debt of its start
+/// (nachschüssig) or at its start on the debt left after the period's clearance (vorschüssig).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InterestTiming {
+    InArrears,
+    InAdvance,
+}
+
+impl Default for InterestTiming {
+    fn default() -> Self {
+        InterestTiming::InArrears
+    }
+}
+
+/// How amounts are rounded to cents.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RoundingMode {
+    HalfUp,
+    /// Banker's rounding.
+    HalfEven,
+    Down,
+    Up,
+}
+
+impl Default for RoundingMode {
+    fn default() -> Self {
+        RoundingMode::HalfUp
+    }
+}
+
+impl RoundingMode {
+    pub fn round(&self, value: Decimal) -> Decimal {
+        let strategy = match self {
+            RoundingMode::HalfUp => RoundingStrategy::RoundHalfUp,
+            RoundingMode::HalfEven => RoundingStrategy::BankersRounding,
+            RoundingMode::Down => RoundingStrategy::RoundDown,
+            RoundingMode::Up => RoundingStrategy::RoundUp,
+        };
+        value.round_dp_with_strategy(2, strategy)
+    }
+}
+
+/// How the simulated follow-up rate after the fixed-interest period is drawn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RateModel {
+    /// Normally distributed around the follow-up rate, or the current rate without one.
+    Normal,
+    /// Random walk from the current rate with monthly steps over the fixed-interest period.
+    RandomWalk,
+}
+
+impl Default for RateModel {
+    fn default() -> Self {
+        RateModel::Normal
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CalcErr {
+    RuntimeZero,
+    /// Special repayments of the given contract year exceed the cap.
+    SpecialRepaymentCapExceeded(i32),
+    /// The disbursement plan does not pay out the loan amount.
+    DisbursementMismatch,
+    /// No value reaches the goal of a goal seek.
+    GoalNotReachable,
+    /// A simulation of follow-up rates needs a fixed-interest period.
+    FixedPeriodMissing,
+    InvalidParams(Vec<InvalidParam>),
+    /// An intermediate value exceeds the range of `Decimal`.
+    Overflow,
+}
+
+/// Input parameter an error refers to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Param {
+    Amount,
+    InterestRate,
+    ClearanceRate,
+    Runtime,
+    RepaymentFreeMonths,
+    FixedPeriod,
+    FollowUpRate,
+    FollowUpClearanceRate,
+    RateChanges,
+    SpecialRepayments,
+    CommitmentRate,
+    ProcessingFee,
+    Disagio,
+    AccountFee,
+    Insurance,
+    EarlyRepayment,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvalidReason {
+    Negative,
+    /// Above `MAX_RATE`.
+    RateTooHigh,
+    Zero,
+    /// Above `MAX_RUNTIME_YEARS`, or a period longer than the runtime.
+    TooLong,
+    /// The payment does not cover the interest, so the debt grows.
+    PaymentBelowInterest,
+    /// A month before the first one of the loan.
+    BeforeStart,
+}
+
+#[derive(Debug, Clone)]
+pub struct InvalidParam {
+    pub param: Param,
+    pub reason: InvalidReason,
+}
+
+/// Highest plausible interest or clearance rate in percent p.a., higher rates are taken as typos.
+pub const MAX_RATE: Decimal = dec!(100);
+
+/// Longest runtime in years.
+pub const MAX_RUNTIME_YEARS: i32 = 100;
+
+/// Tranche of a construction loan paid out at the start of `month`.
+#[derive(Debug, Clone)]
+pub struct Disbursement {
+    pub month: i32,
+    pub amount: Decimal,
+}
+
+/// Fixed-interest period (Zinsbindung) after which the annuity continues on follow-up terms.
+/// Without a follow-up rate the original rate is assumed; without a follow-up clearance rate
+/// the payment is kept, or re-solved for the remaining runtime in `AnnuityMode::Runtime`.
+#[derive(Debug, Default, Clone)]
+pub struct FixedPeriod {
+    pub years: i32,
+    pub follow_up_rate: Option<Decimal>,
+    pub follow_up_clearance_rate: Option<Decimal>,
+}
+
+/// How the payment reacts to a scheduled interest rate change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RateChangeMode {
+    KeepPayment,
+    /// Re-solve the payment for the remaining runtime in `AnnuityMode::Runtime`,
+    /// otherwise apply the clearance rate to the remaining debt.
+    RecomputePayment,
+}
+
+impl Default for RateChangeMode {
+    fn default() -> Self {
+        RateChangeMode::KeepPayment
+    }
+}
+
+/// New interest rate in percent per year, effective from `month` on.
+#[derive(Debug, Clone)]
+pub struct RateChange {
+    pub month: i32,
+    pub interest_rate: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub enum SpecialAmount {
+    Fixed(Decimal),
+    /// Percent of the original loan amount.
+    PercentOfPrincipal(Decimal),
+}
+
+impl SpecialAmount {
+    fn value(&self, principal: Decimal) -> Decimal {
+        match self {
+            SpecialAmount::Fixed(amount) => *amount,
+            SpecialAmount::PercentOfPrincipal(percent) => principal * percent / dec!(100),
+        }
+    }
+}
+
+/// Unscheduled repayment (Sondertilgung) paid in addition to the regular rate.
+#[derive(Debug, Clone)]
+pub struct SpecialRepayment {
+    pub month: i32,
+    pub amount: SpecialAmount,
+    /// Repeat every twelve months after `month`.
+    pub yearly: bool,
+}
+
+impl SpecialRepayment {
+    fn amount_in(&self, month: i32, principal: Decimal) -> Decimal {
+        let due = month == self.month || (self.yearly && month > self.month && (month - self.month) % 12 == 0);
+        if !due {
+            return dec!(0);
+        }
+        self.amount.value(principal)
+    }
+}
+
+/// Repayment of the whole remaining debt after the payment of `month` before the end of the
+/// fixed-interest period. `reinvestment_rate` is the rate in percent p.a. the bank can lend the
+/// money at again.
+#[derive(Debug, Clone)]
+pub struct EarlyRepayment {
+    pub month: i32,
+    pub reinvestment_rate: Decimal,
+}
+
+/// Repayment subsidy (Tilgungszuschuss), e.g. of a KfW loan, credited to the loan in `month`,
+/// or after the construction phase if it falls into it. It reduces the remaining debt without
+/// changing the payment, so the loan is paid off earlier.
+#[derive(Debug, Clone)]
+pub struct RepaymentGrant {
+    pub month: i32,
+    pub amount: SpecialAmount,
+}
+
+/// Savings phase of a building savings contract (Bausparvertrag). Rates and the
+/// minimum balance are given in percent, the latter and the fee of the contract sum.
+/// The rating (Bewertungszahl) is the sum of the monthly balances divided by the contract sum.
+#[derive(Debug, Default, Clone)]
+pub struct BuildingSavingsParams {
+    pub monthly_deposit: Decimal,
+    pub deposit_rate: Decimal,
+    pub contract_fee: Decimal,
+    pub minimum_balance: Decimal,
+    pub minimum_rating: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RepaymentVehicleKind {
+    /// Returns are credited monthly, the fee is charged on every contribution.
+    SavingsPlan,
+    /// Interest is credited yearly, the fee is charged once on the loan amount as contract sum.
+    BuildingSavings,
+}
+
+/// Savings accumulated alongside a bullet loan to repay it at maturity.
+/// The return rate and fee are given in percent.
+#[derive(Debug, Clone)]
+pub struct RepaymentVehicle {
+    pub kind: RepaymentVehicleKind,
+    pub monthly_contribution: Decimal,
+    pub return_rate: Decimal,
+    pub fee: Decimal,
+}
+
+/// Costs of a loan besides the nominal interest. The processing fee and the disagio,
+/// given in percent of the amount, are withheld from the payout; account fee and
+/// mandatory insurance are charged monthly.
+#[derive(Debug, Default, Clone)]
+pub struct LoanFees {
+    pub processing_fee: Decimal,
+    pub disagio: Decimal,
+    pub account_fee: Decimal,
+    pub insurance: Decimal,
+}
+
+impl LoanFees {
+    fn upfront(&self, amount: Decimal) -> Decimal {
+        self.processing_fee + amount * self.disagio / dec!(100)
+    }
+
+    fn monthly(&self) -> Decimal {
+        self.account_fee + self.insurance
+    }
+}
+
+/// Amount of money.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Money(pub Decimal);
+
+/// Rate in percent per year.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Percent(pub Decimal);
+
+/// Duration in months.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Duration(pub i32);
+
+impl Duration {
+    /// Rounds to whole months. Saturates instead of overflowing, so the runtime limit of the
+    /// validation reports it.
+    pub fn from_years(years: Decimal) -> Self {
+        let months = years.checked_mul(dec!(12)).and_then(|m| m.round().to_i32());
+        Duration(months.unwrap_or(if years < dec!(0) { i32::MIN } else { i32::MAX }))
+    }
+
+    pub fn months(&self) -> i32 {
+        self.0
+    }
+
+    /// Years rounded to two decimals, a runtime of 150 months is 12.5 years.
+    pub fn years(&self) -> Decimal {
+        (Decimal::from(self.0) / dec!(12)).round_dp(2)
+    }
+}
+
+/// Typed input of a loan calculation. Rates are given in percent per year.
+/// Building savings ignore payment frequency and interest conventions and are always paid monthly.
+#[derive(Debug, Default, Clone)]
+pub struct LoanParams {
+    pub loan_type: LoanType,
+    pub annuity_mode: AnnuityMode,
+    pub amount: Money,
+    pub interest_rate: Percent,
+    pub clearance_rate: Percent,
+    /// Payment periods cover whole months of the runtime, a started period is not paid.
+    pub runtime: Duration,
+    /// Interest-only lead-in (tilgungsfreie Anlaufzeit) in months.
+    pub repayment_free_months: i32,
+    pub fixed_period: Option<FixedPeriod>,
+    pub rate_changes: Vec<RateChange>,
+    pub rate_change_mode: RateChangeMode,
+    pub special_repayments: Vec<SpecialRepayment>,
+    /// Contractual limit of special repayments per year in percent of the loan amount.
+    pub special_repayment_cap: Option<Decimal>,
+    pub building_savings: BuildingSavingsParams,
+    pub repayment_vehicle: Option<RepaymentVehicle>,
+    pub fees: LoanFees,
+    pub payment_frequency: PaymentFrequency,
+    pub day_count: DayCount,
+    pub interest_timing: InterestTiming,
+    /// Payout date of the loan. Without it the schedule has no due dates.
+    pub start_date: Option<NaiveDate>,
+    /// Day of month payments are due, defaults to the day of `start_date`.
+    pub payment_day: Option<u32>,
+    pub rounding_mode: RoundingMode,
+    /// Disbursement plan of a construction loan, empty if the loan is paid out at once.
+    /// Only annuity loans are paid out in tranches.
+    pub disbursements: Vec<Disbursement>,
+    /// Commitment interest (Bereitstellungszinsen) on the undrawn amount in percent p.a.
+    pub commitment_rate: Decimal,
+    /// Months from the start without commitment interest.
+    pub commitment_free_months: i32,
+    /// Only used by annuity and linear loans.
+    pub repayment_grant: Option<RepaymentGrant>,
+    /// Only used by annuity loans.
+    pub early_repayment: Option<EarlyRepayment>,
+}
+
+impl LoanParams {
+    fn rate_change(&self, first_month: i32, last_month: i32) -> Option<&RateChange> {
+        self.rate_changes.iter().rev().find(|c| first_month <= c.month && c.month <= last_month)
+    }
+
+    fn special_repayment(&self, month: i32) -> Decimal {
+        self.special_repayments.iter()
+            .map(|s| s.amount_in(month, self.amount.0))
+            .fold(dec!(0), |acc, s| acc + s)
+    }
+
+    fn periods(&self) -> i32 {
+        self.runtime.months() / self.payment_frequency.months()
+    }
+
+    /// Due date of the payment in `month`, month 0 being the start date. A payment day beyond
+    /// the end of a month falls on its last day.
+    pub fn due_date(&self, month: i32) -> Option<NaiveDate> {
+        let start = self.start_date?;
+        if month == 0 {
+            return Some(start);
+        }
+        let months = start.year() * 12 + start.month0() as i32 + month;
+        let day = self.payment_day.unwrap_or_else(|| start.day());
+        (1..=day).rev().find_map(|d| NaiveDate::from_ymd_opt(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, d))
+    }
+
+    /// Month of the last due date on or before `date`. A date between the start date and the
+    /// first due date falls into month 1, a date before the start into month 0.
+    pub fn month_at(&self, date: NaiveDate) -> Option<i32> {
+        let start = self.start_date?;
+        if date < start {
+            return Some(0);
+        }
+        let mut month = (date.year() - start.year()) * 12 + date.month() as i32 - start.month() as i32 + 1;
+        while month > 1 && self.due_date(month)? > date {
+            month -= 1;
+        }
+        Some(month.max(1))
+    }
+
+    /// Interest rate of the period covering `first_month..=last_month` for the annual rate `interest_rate`
+    /// given as fraction. Days are counted between the due dates if there is a start date.
+    fn period_rate(&self, interest_rate: Decimal, first_month: i32, last_month: i32) -> Decimal {
+        match (self.due_date(first_month - 1), self.due_date(last_month)) {
+            (Some(from), Some(to)) => {
+                let days = match self.day_count {
+                    DayCount::Thirty360 => days_30e_360(from, to),
+                    DayCount::Act360 | DayCount::Act365 => to.signed_duration_since(from).num_days(),
+                };
+                let basis = match self.day_count {
+                    DayCount::Act365 => dec!(365),
+                    _ => dec!(360),
+                };
+                interest_rate * Decimal::from(days) / basis
+            }
+            _ => self.nominal_period_rate(interest_rate, last_month - first_month + 1),
+        }
+    }
+
+    /// Interest rate of `months` assuming every month has 365 / 12 days.
+    fn nominal_period_rate(&self, interest_rate: Decimal, months: i32) -> Decimal {
+        let months = Decimal::from(months);
+        let year_fraction = match self.day_count {
+            DayCount::Thirty360 | DayCount::Act365 => months / dec!(12),
+            DayCount::Act360 => months * dec!(365) / dec!(12) / dec!(360),
+        };
+        interest_rate * year_fraction
+    }
+
+    /// Interest rate of a regular payment period for solving payments.
+    fn regular_period_rate(&self, interest_rate: Decimal) -> Decimal {
+        self.nominal_period_rate(interest_rate, self.payment_frequency.months())
+    }
+
+    /// Payment per period that pays off `amount` in the periods `first_period..=last_period` with
+    /// the annual `interest_rate` given as fraction. With a start date the interest of each period
+    /// is counted between its due dates like in the schedule.
+    fn dated_annuity_payment(&self, amount: Decimal, interest_rate: Decimal, first_period: i32, last_period: i32) -> Result<Decimal, CalcErr> {
+        if self.start_date.is_none() {
+            return annuity_payment(amount, self.regular_period_rate(interest_rate), (last_period - first_period + 1).max(0) as u32, &self.interest_timing);
+        }
+        // Present value of a payment of 1 in every period.
+        let step = self.payment_frequency.months();
+        let mut discount = dec!(1);
+        let mut present_value = dec!(0);
+        for period in first_period..=last_period {
+            let rate = self.period_rate(interest_rate, period * step - step + 1, period * step);
+            match self.interest_timing {
+                InterestTiming::InArrears => {
+                    discount /= dec!(1) + rate;
+                    present_value += discount;
+                }
+                InterestTiming::InAdvance => {
+                    present_value += discount;
+                    discount *= dec!(1) - rate;
+                }
+            }
+        }
+        if present_value <= dec!(0) {
+            return Err(CalcErr::RuntimeZero);
+        }
+        amount.checked_div(present_value).ok_or(CalcErr::Overflow)
+    }
+
+    fn round(&self, value: Decimal) -> Decimal {
+        self.rounding_mode.round(value)
+    }
+
+    /// Interest of a period with `rate` on `remaining` when `cleared_amount` is repaid, in cents.
+    fn interest(&self, remaining: Decimal, cleared_amount: Decimal, rate: Decimal) -> Decimal {
+        match self.interest_timing {
+            InterestTiming::InArrears => self.round(remaining * rate),
+            InterestTiming::InAdvance => self.round((remaining - cleared_amount) * rate),
+        }
+    }
+
+    /// Splits `payment` into interest and clearance of `remaining` for a period with `rate`.
+    /// The interest is rounded to cents, or the clearance if interest is paid in advance.
+    fn split_payment(&self, remaining: Decimal, payment: Decimal, rate: Decimal) -> (Decimal, Decimal) {
+        match self.interest_timing {
+            InterestTiming::InArrears => {
+                let interest = self.round(remaining * rate);
+                (interest, payment - interest)
+            }
+            InterestTiming::InAdvance => {
+                let cleared_amount = self.round((payment - remaining * rate) / (dec!(1) - rate));
+                (payment - cleared_amount, cleared_amount)
+            }
+        }
+    }
+
+    /// Grant credited in `first_month..=last_month`, at most `remaining`.
+    fn repayment_grant(&self, first_month: i32, last_month: i32, remaining: Decimal) -> Decimal {
+        match &self.repayment_grant {
+            Some(grant) if first_month <= grant.month && grant.month <= last_month => {
+                self.round(grant.amount.value(self.amount.0)).min(remaining).max(dec!(0))
+            }
+            _ => dec!(0),
+        }
+    }
+
+    fn disbursed(&self, month: i32) -> Decimal {
+        self.disbursements.iter()
+            .filter(|d| d.month == month)
+            .fold(dec!(0), |acc, d| acc + d.amount)
+    }
+
+    /// Month of the last disbursement, 0 if the loan is paid out at once.
+    fn construction_months(&self) -> i32 {
+        self.disbursements.iter().map(|d| d.month).max().unwrap_or(0)
+    }
+
+    fn validate_disbursements(&self) -> Result<(), CalcErr> {
+        if self.disbursements.is_empty() {
+            return Ok(());
+        }
+        let sum = self.disbursements.iter().fold(dec!(0), |acc, d| acc + d.amount);
+        if sum != self.amount.0 || self.disbursements.iter().any(|d| d.month < 1) {
+            return Err(CalcErr::DisbursementMismatch);
+        }
+        Ok(())
+    }
+
+    /// Checks the parameters for negative, zero and implausibly high values.
+    fn validate(&self) -> Result<(), CalcErr> {
+        let mut invalid = vec![];
+        let mut check = |param: Param, reason: Option<InvalidReason>| {
+            if let Some(reason) = reason {
+                invalid.push(InvalidParam { param, reason });
+            }
+        };
+        let rate = |rate: Decimal| if rate < dec!(0) {
+            Some(InvalidReason::Negative)
+        }else if rate > MAX_RATE {
+            Some(InvalidReason::RateTooHigh)
+        }else {
+            None
+        };
+        let non_negative = |value: Decimal| if value < dec!(0) { Some(InvalidReason::Negative) } else { None };
+
+        check(Param::Amount, non_negative(self.amount.0));
+        check(Param::InterestRate, rate(self.interest_rate.0));
+        check(Param::ClearanceRate, rate(self.clearance_rate.0));
+        check(Param::Runtime, if self.runtime.months() <= 0 {
+            Some(InvalidReason::Zero)
+        }else if self.runtime.months() > MAX_RUNTIME_YEARS * 12 {
+            Some(InvalidReason::TooLong)
+        }else {
+            None
+        });
+        check(Param::RepaymentFreeMonths, non_negative(Decimal::from(self.repayment_free_months)));
+        if let Some(fixed) = &self.fixed_period {
+            check(Param::FixedPeriod, if fixed.years <= 0 {
+                Some(InvalidReason::Zero)
+            }else if fixed.years > MAX_RUNTIME_YEARS || fixed.years * 12 > self.runtime.months() {
+                Some(InvalidReason::TooLong)
+            }else {
+                None
+            });
+            check(Param::FollowUpRate, fixed.follow_up_rate.and_then(rate));
+            check(Param::FollowUpClearanceRate, fixed.follow_up_clearance_rate.and_then(rate));
+        }
+        check(Param::RateChanges, self.rate_changes.iter().filter_map(|c| rate(c.interest_rate)).next());
+        check(Param::CommitmentRate, rate(self.commitment_rate));
+        check(Param::ProcessingFee, non_negative(self.fees.processing_fee));
+        check(Param::Disagio, rate(self.fees.disagio));
+        check(Param::AccountFee, non_negative(self.fees.account_fee));
+        check(Param::Insurance, non_negative(self.fees.insurance));
+        check(Param::SpecialRepayments, self.special_repayments.iter().filter_map(|s| match s.amount {
+            _ if s.month < 1 => Some(InvalidReason::BeforeStart),
+            SpecialAmount::Fixed(amount) | SpecialAmount::PercentOfPrincipal(amount) if amount < dec!(0) => Some(InvalidReason::Negative),
+            SpecialAmount::Fixed(amount) | SpecialAmount::PercentOfPrincipal(amount) if amount == dec!(0) => Some(InvalidReason::Zero),
+            _ => None,
+        }).next());
+        if let Some(early_repayment) = &self.early_repayment {
+            check(Param::EarlyRepayment, if early_repayment.month < 1 { Some(InvalidReason::BeforeStart) } else { None });
+        }
+        if invalid.is_empty() {
+            Ok(())
+        }else {
+            Err(CalcErr::InvalidParams(invalid))
+        }
+    }
+
+    fn validate_special_repayments(&self) -> Result<(), CalcErr> {
+        if let Some(cap) = self.special_repayment_cap {
+            let max = self.amount.0 * cap / dec!(100);
+            for year in 0..(self.runtime.months() + 11) / 12 {
+                let sum = (1..=12)
+                    .map(|m| self.special_repayment(year * 12 + m))
+                    .fold(dec!(0), |acc, s| acc + s);
+                if sum > max {
+                    return Err(CalcErr::SpecialRepaymentCapExceeded(year + 1));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct CalcResultOverview {
+    pub overall: CalcResult,
+    /// Regular payment per payment period.
+    pub monthly_rate: Decimal,
+    /// Initial clearance rate in percent per year.
+    pub clearance_rate: Decimal,
+    /// Month in which the remaining debt reached zero, if it did within the runtime.
+    pub payoff_month: Option<i32>,
+    /// Interest-only rate paid during the repayment-free start period.
+    pub repayment_free_monthly_rate: Option<Decimal>,
+    /// Remaining debt at the end of the fixed-interest period.
+    pub fixed_period_remaining: Option<Decimal>,
+    /// Monthly rate paid after the fixed-interest period.
+    pub follow_up_monthly_rate: Option<Decimal>,
+    /// Savings of the special repayments compared to the plain schedule.
+    pub special_repayment_savings: Option<SpecialRepaymentSavings>,
+    pub building_savings: Option<BuildingSavingsResult>,
+    pub maturity: Option<MaturityResult>,
+    pub construction: Option<ConstructionResult>,
+    pub early_repayment: Option<EarlyRepaymentResult>,
+    /// Fees withheld from the payout.
+    pub upfront_fees: Decimal,
+    /// Effective annual interest rate in percent including all fees.
+    pub effective_rate: Option<Decimal>,
+    pub months: Vec<Box<CalcResult>>
+}
+
+/// Allotment (Zuteilung) of a building savings contract.
+#[derive(Default, Debug)]
+pub struct BuildingSavingsResult {
+    pub allotment_month: i32,
+    /// Savings balance paid out at allotment.
+    pub balance: Decimal,
+    pub loan_amount: Decimal,
+    pub loan_monthly_rate: Decimal,
+}
+
+/// Construction phase of a loan paid out in tranches, before the regular payments start.
+#[derive(Default, Debug)]
+pub struct ConstructionResult {
+    pub months: i32,
+    /// Interest on the drawn amount during construction.
+    pub interest: Decimal,
+    pub commitment_interest: Decimal,
+}
+
+/// Estimated early repayment penalty (Vorfälligkeitsentschädigung).
+#[derive(Default, Debug)]
+pub struct EarlyRepaymentResult {
+    pub month: i32,
+    pub date: Option<NaiveDate>,
+    /// Remaining debt to repay after the payment of `month`.
+    pub remaining: Decimal,
+    pub penalty: Decimal,
+}
+
+/// Outcome of a bullet loan at maturity.
+#[derive(Default, Debug)]
+pub struct MaturityResult {
+    /// Balance of the repayment vehicle at maturity.
+    pub vehicle_balance: Decimal,
+    /// Part of the principal not covered by the repayment vehicle.
+    pub shortfall: Decimal,
+    /// Part of the vehicle balance left after repaying the principal.
+    pub surplus: Decimal,
+}
+
+#[derive(Default, Debug)]
+pub struct SpecialRepaymentSavings {
+    pub interest: Decimal,
+    /// `None` if the loan is not paid off with or without special repayments.
+    pub months: Option<i32>,
+}
+
+#[derive(Default, Debug)]
+pub struct CalcResult {
+    /// Month of the payment, counted from 1.
+    pub month: i32,
+    /// Remaining debt after this month's payment.
+    pub remaining: Decimal,
+    pub payment: Decimal,
+    pub paid_interest: Decimal,
+    /// Part of `cleared_amount` paid as special repayment.
+    pub special_repayment: Decimal,
+    pub cleared_amount: Decimal,
+    /// Fees charged in addition to interest, already deducted from `cleared_amount`.
+    pub fees: Decimal,
+    /// Deposit interest credited to a savings balance, included in `cleared_amount`.
+    pub earned_interest: Decimal,
+    /// Commitment interest on the undrawn amount of a construction loan.
+    pub commitment_interest: Decimal,
+    /// Part of `cleared_amount` credited as repayment grant, not paid by the borrower.
+    pub grant: Decimal,
+    /// Due date of the payment if the loan has a start date.
+    pub date: Option<NaiveDate>,
+    /// Savings balance after this month, e.g. of a building savings contract before allotment.
+    pub savings_balance: Decimal,
+}
+
+impl CalcResultOverview {
+    fn new(amount: Decimal) -> Self {
+        Self {
+            overall: CalcResult {
+                remaining: amount,
+                ..CalcResult::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Cost of the credit: interest, commitment interest and fees less the repayment grant.
+    pub fn cost(&self) -> Decimal {
+        self.overall.paid_interest + self.overall.commitment_interest + self.overall.fees + self.upfront_fees - self.overall.grant
+    }
+
+    fn apply_fees(&mut self, params: &LoanParams, period_months: i32) {
+        let fees = &params.fees;
+        self.upfront_fees = params.round(fees.upfront(params.amount.0));
+        let monthly = fees.monthly();
+        if monthly == dec!(0) {
+            return;
+        }
+        let period_fees = params.round(monthly * Decimal::from(period_months));
+        self.monthly_rate += period_fees;
+        if let Some(rate) = self.repayment_free_monthly_rate.as_mut() {
+            *rate += period_fees;
+        }
+        if let Some(rate) = self.follow_up_monthly_rate.as_mut() {
+            *rate += period_fees;
+        }
+        let mut last_month = 0;
+        for month in self.months.iter_mut() {
+            let fees = params.round(monthly * Decimal::from(month.month - last_month));
+            last_month = month.month;
+            month.payment += fees;
+            month.fees += fees;
+            self.overall.payment += fees;
+            self.overall.fees += fees;
+        }
+    }
+
+    fn apply_dates(&mut self, params: &LoanParams) {
+        for month in self.months.iter_mut() {
+            month.date = params.due_date(month.month);
+        }
+        self.overall.date = params.due_date(self.overall.month);
+    }
+
+    /// Adds a schedule row given in cents to the totals and the schedule, so the totals
+    /// are exactly the sums of the rows.
+    fn push_month(&mut self, month: CalcResult) {
+        self.overall.month = month.month;
+        self.overall.remaining = month.remaining;
+        self.overall.payment += month.payment;
+        self.overall.paid_interest += month.paid_interest;
+        self.overall.cleared_amount += month.cleared_amount;
+        self.overall.special_repayment += month.special_repayment;
+        self.overall.fees += month.fees;
+        self.overall.earned_interest += month.earned_interest;
+        self.overall.commitment_interest += month.commitment_interest;
+        self.overall.grant += month.grant;
+        self.overall.savings_balance = month.savings_balance;
+        self.months.push(Box::new(month));
+    }
+}
+
+/// Days between `from` and `to` following the 30E/360 convention.
+fn days_30e_360(from: NaiveDate, to: NaiveDate) -> i64 {
+    let d1 = from.day().min(30) as i64;
+    let d2 = to.day().min(30) as i64;
+    360 * (to.year() - from.year()) as i64 + 30 * (to.month() as i64 - from.month() as i64) + d2 - d1
+}
+
+/// Whether clearing `cleared_amount` pays off `remaining` up to the last cent.
+fn pays_off(remaining: Decimal, cleared_amount: Decimal) -> bool {
+    (remaining - cleared_amount).round_dp(2) <= dec!(0)
+}
+
+/// Whether `residual` left after the last of `periods` payments rounded to cents is only
+/// the accumulated rounding difference, which the last payment absorbs.
+fn is_rounding_difference(residual: Decimal, periods: i32) -> bool {
+    residual.abs() <= Decimal::from(periods) * dec!(0.01)
+}
+
+/// Whether `residual` is at most what rounding a payment solved for `periods` with `period_rate`
+/// to cents can leave, up to a cent per period grown by the interest.
+fn is_solved_rounding_difference(residual: Decimal, period_rate: Decimal, periods: i32) -> bool {
+    if period_rate == dec!(0) {
+        return is_rounding_difference(residual, periods);
+    }
+    pow(dec!(1) + period_rate, periods.max(0) as u32)
+        .map(|growth| residual.abs() <= (growth - dec!(1)) / period_rate * dec!(0.01))
+        .unwrap_or(false)
+}
+
+pub fn calc(params: &LoanParams) -> Result<CalcResultOverview, CalcErr> {
+    params.validate()?;
+    let mut result = match params.loan_type {
+        LoanType::Annuity => calc_annuity(params)?,
+        LoanType::BuildingSavings => calc_building_saving(params),
+        LoanType::Linear => calc_linear(params)?,
+        LoanType::Bullet => calc_bullet(params)?,
+    };
+    let period_months = match params.loan_type {
+        LoanType::BuildingSavings => 1,
+        _ => params.payment_frequency.months(),
+    };
+    result.apply_fees(params, period_months);
+    result.apply_dates(params);
+    if let LoanType::Annuity = params.loan_type {
+        check_payment_covers_interest(params, &result)?;
+        result.early_repayment = early_repayment_penalty(params, &result);
+    }
+    if !matches!(params.loan_type, LoanType::BuildingSavings) {
+        result.effective_rate = effective_rate(params, &result);
+    }
+    Ok(result)
+}
+
+/// Fails if a regular annuity payment after the construction phase is lower than its interest,
+/// naming the input that set the payment.
+fn check_payment_covers_interest(params: &LoanParams, result: &CalcResultOverview) -> Result<(), CalcErr> {
+    let construction_months = result.construction.as_ref().map(|c| c.months).unwrap_or(0);
+    let month = result.months.iter()
+        .filter(|m| m.month > construction_months)
+        .find(|m| m.cleared_amount - m.special_repayment - m.grant < dec!(0))
+        .map(|m| m.month);
+    let month = match month {
+        Some(month) => month,
+        None => return Ok(()),
+    };
+    let fixed_months = params.fixed_period.as_ref().map(|f| f.years * 12);
+    let rate_change = params.rate_changes.iter().filter(|c| c.month <= month).map(|c| c.month).max();
+    let param = match (fixed_months, rate_change) {
+        (Some(fixed_months), rate_change) if fixed_months < month && rate_change.map(|c| c <= fixed_months).unwrap_or(true) => Param::FollowUpRate,
+        (_, Some(_)) => Param::RateChanges,
+        _ => Param::ClearanceRate,
+    };
+    Err(CalcErr::InvalidParams(vec![InvalidParam {
+        param,
+        reason: InvalidReason::PaymentBelowInterest,
+    }]))
+}
+
+/// Effective annual rate following the EU consumer credit directive (PAngV): the rate `X`
+/// at which the payouts equal the sum of all payments discounted with `(1 + X)^(-t)`, `t`
+/// in years with a month counting as 1/12 year. Remaining debt at the end of the schedule
+/// is treated as paid with the last payment, tranches as paid out at the start of their month.
+/// A repayment grant is not paid by the borrower and lowers the rate.
+///
+/// Not applicable to building savings, whose payout does not happen at the start.
+pub fn effective_rate(params: &LoanParams, result: &CalcResultOverview) -> Option<Decimal> {
+    let payouts = if result.construction.is_none() {
+        vec![(0, params.amount.0 - result.upfront_fees)]
+    }else {
+        let mut payouts = vec![(0, -result.upfront_fees)];
+        payouts.extend(params.disbursements.iter().map(|d| (d.month - 1, d.amount)));
+        payouts
+    };
+    let mut last_month = 0;
+    let mut cash_flows = result.months.iter()
+        .map(|m| {
+            let months = m.month - last_month;
+            last_month = m.month;
+            (months, m.paid_interest + m.cleared_amount - m.grant + m.commitment_interest + params.fees.monthly() * Decimal::from(months))
+        })
+        .collect::<Vec<(i32, Decimal)>>();
+    if let Some(last) = cash_flows.last_mut() {
+        last.1 += result.overall.remaining;
+    }
+    if payouts.iter().fold(dec!(0), |acc, p| acc + p.1) <= dec!(0) || cash_flows.is_empty() {
+        return None;
+    }
+
+    // Present value of the payments minus the payouts, falling with the monthly discount factor `q`.
+    let surplus = |q: Decimal| -> Option<Decimal> {
+        let v = dec!(1) / q;
+        let mut discount = dec!(1);
+        let mut value = dec!(0);
+        for (months, cash_flow) in &cash_flows {
+            for _ in 0..*months {
+                discount = discount.checked_mul(v)?;
+            }
+            value = value.checked_add(cash_flow.checked_mul(discount)?)?;
+        }
+        for (month, payout) in &payouts {
+            let mut discount = dec!(1);
+            for _ in 0..*month {
+                discount = discount.checked_mul(v)?;
+            }
+            value = value.checked_sub(payout.checked_mul(discount)?)?;
+        }
+        Some(value)
+    };
+
+    let mut low = dec!(0.9);
+    let mut high = dec!(1.1);
+    if surplus(low)? < dec!(0) || surplus(high)? > dec!(0) {
+        return None;
+    }
+    for _ in 0..60 {
+        let mid = (low + high) / dec!(2);
+        if surplus(mid)? > dec!(0) {
+            low = mid;
+        }else {
+            high = mid;
+        }
+    }
+    Some((pow((low + high) / dec!(2), 12)? - dec!(1)) * dec!(100))
+}
+
+/// Remaining debt of an annuity loan with `amount` and a payment of `budget` per period, plus
+/// the amount by which the last payment exceeds the budget.
+fn remaining_with_budget(params: &LoanParams, amount: Decimal, budget: Decimal) -> Result<Decimal, CalcErr> {
+    let clearance_rate = if amount > dec!(0) {
+        budget * params.payment_frequency.per_year() / amount * dec!(100) - params.interest_rate.0
+    }else {
+        dec!(0)
+    };
+    let result = calc_annuity(&LoanParams {
+        amount: Money(amount),
+        annuity_mode: AnnuityMode::ClearanceRate,
+        clearance_rate: Percent(clearance_rate),
+        disbursements: vec![],
+        ..params.clone()
+    })?;
+    let excess = result.months.last()
+        .map(|m| (m.payment - m.special_repayment - budget).max(dec!(0)))
+        .unwrap_or(dec!(0));
+    Ok(result.overall.remaining + excess)
+}
+
+#[derive(Default, Debug)]
+pub struct MaxAmount {
+    pub amount: Decimal,
+    /// Initial clearance rate in percent p.a. with which the payment uses up the budget.
+    pub clearance_rate: Decimal,
+}
+
+/// Largest annuity loan whose payment including monthly fees fits `budget` per payment period
+/// and which leaves at most `target_remaining` after the runtime. The loan is assumed to be
+/// paid out at once.
+pub fn max_amount(params: &LoanParams, budget: Decimal, target_remaining: Decimal) -> Result<MaxAmount, CalcErr> {
+    params.validate()?;
+    let budget = budget - params.fees.monthly() * Decimal::from(params.payment_frequency.months());
+    if budget <= dec!(0) || target_remaining < dec!(0) {
+        return Err(CalcErr::GoalNotReachable);
+    }
+    let mut low = dec!(0);
+    let mut high = budget.checked_mul(Decimal::from(params.periods()))
+        .and_then(|total| total.checked_add(target_remaining))
+        .ok_or(CalcErr::Overflow)?;
+    let amount = if remaining_with_budget(params, high, budget)? <= target_remaining {
+        params.round(high)
+    }else {
+        for _ in 0..40 {
+            let mid = (low + high) / dec!(2);
+            if remaining_with_budget(params, mid, budget)? > target_remaining {
+                high = mid;
+            }else {
+                low = mid;
+            }
+        }
+        low.round_dp_with_strategy(2, RoundingStrategy::RoundDown)
+    };
+    // Rounded down so the payment does not exceed the budget.
+    let clearance_rate = if amount > dec!(0) {
+        (budget * params.payment_frequency.per_year() / amount * dec!(100) - params.interest_rate.0)
+            .round_dp_with_strategy(6, RoundingStrategy::RoundDown)
+    }else {
+        dec!(0)
+    };
+    Ok(MaxAmount {
+        amount,
+        clearance_rate,
+    })
+}
+
+/// Clearance rate in percent p.a. with which an annuity loan leaves at most `target_remaining`
+/// after the runtime.
+pub fn required_clearance_rate(params: &LoanParams, target_remaining: Decimal) -> Result<Decimal, CalcErr> {
+    params.validate()?;
+    let remaining = |clearance_rate: Decimal| -> Result<Decimal, CalcErr> {
+        Ok(calc_annuity(&LoanParams {
+            annuity_mode: AnnuityMode::ClearanceRate,
+            clearance_rate: Percent(clearance_rate),
+            ..params.clone()
+        })?.overall.remaining)
+    };
+    let mut low = dec!(0);
+    let mut high = dec!(100);
+    if remaining(low)? <= target_remaining {
+        return Ok(low);
+    }
+    if remaining(high)? > target_remaining {
+        return Err(CalcErr::GoalNotReachable);
+    }
+    for _ in 0..40 {
+        let mid = (low + high) / dec!(2);
+        if remaining(mid)? > target_remaining {
+            low = mid;
+        }else {
+            high = mid;
+        }
+    }
+    Ok(high.round_dp_with_strategy(2, RoundingStrategy::RoundUp))
+}
+
+#[derive(Default, Debug)]
+pub struct ImpliedRate {
+    /// Nominal interest rate in percent p.a.
+    pub nominal: Decimal,
+    /// Effective rate in percent p.a. including fees.
+    pub effective: Option<Decimal>,
+}
+
+/// Interest rate at which an annuity loan is paid off within the runtime by `payment` per
+/// payment period including monthly fees.
+pub fn implied_interest_rate(params: &LoanParams, payment: Decimal) -> Result<ImpliedRate, CalcErr> {
+    let payment = payment - params.fees.monthly() * Decimal::from(params.payment_frequency.months());
+    let repayment_free_periods = params.repayment_free_months.max(0) / params.payment_frequency.months();
+    let payment_at = |interest_rate: Decimal| {
+        params.dated_annuity_payment(params.amount.0, interest_rate / dec!(100), repayment_free_periods + 1, params.periods())
+    };
+    let mut low = dec!(0);
+    let mut high = dec!(100);
+    if payment_at(low)? > payment || payment_at(high)? < payment {
+        return Err(CalcErr::GoalNotReachable);
+    }
+    for _ in 0..60 {
+        let mid = (low + high) / dec!(2);
+        if payment_at(mid)? < payment {
+            low = mid;
+        }else {
+            high = mid;
+        }
+    }
+    let nominal = ((low + high) / dec!(2)).round_dp(4);
+    let result = calc(&LoanParams {
+        annuity_mode: AnnuityMode::Runtime,
+        interest_rate: Percent(nominal),
+        ..params.clone()
+    })?;
+    Ok(ImpliedRate {
+        nominal,
+        effective: result.effective_rate,
+    })
+}
+
+/// Outcome of an annuity loan for one combination of interest and clearance rate.
+#[derive(Default, Debug, Clone)]
+pub struct SensitivityCell {
+    pub monthly_rate: Decimal,
+    pub paid_interest: Decimal,
+    pub remaining: Decimal,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct SensitivityGrid {
+    pub interest_rates: Vec<Decimal>,
+    /// Empty in runtime mode, where the clearance rate follows from the runtime and each
+    /// row has a single cell.
+    pub clearance_rates: Vec<Decimal>,
+    /// Cells by interest rate and clearance rate, `None` if the loan cannot be calculated.
+    pub cells: Vec<Vec<Option<SensitivityCell>>>,
+}
+
+/// Recalculates an annuity loan with the interest and clearance rate shifted by the given
+/// offsets in percentage points. Negative rates are left out.
+pub fn sensitivity_grid(params: &LoanParams, interest_offsets: &[Decimal], clearance_offsets: &[Decimal]) -> Result<SensitivityGrid, CalcErr> {
+    params.validate()?;
+    let interest_rates = interest_offsets.iter()
+        .map(|o| params.interest_rate.0 + o)
+        .filter(|r| *r >= dec!(0))
+        .collect::<Vec<Decimal>>();
+    let clearance_rates = match params.annuity_mode {
+        AnnuityMode::ClearanceRate => clearance_offsets.iter()
+            .map(|o| params.clearance_rate.0 + o)
+            .filter(|r| *r > dec!(0))
+            .collect::<Vec<Decimal>>(),
+        AnnuityMode::Runtime => vec![],
+    };
+    let cell = |interest_rate: Decimal, clearance_rate: Decimal| {
+        calc_annuity(&LoanParams {
+            interest_rate: Percent(interest_rate),
+            clearance_rate: Percent(clearance_rate),
+            ..params.clone()
+        }).ok().map(|r| SensitivityCell {
+            monthly_rate: r.monthly_rate,
+            paid_interest: r.overall.paid_interest,
+            remaining: r.overall.remaining,
+        })
+    };
+    let cells = interest_rates.iter()
+        .map(|i| match params.annuity_mode {
+            AnnuityMode::ClearanceRate => clearance_rates.iter().map(|c| cell(*i, *c)).collect(),
+            AnnuityMode::Runtime => vec![cell(*i, params.clearance_rate.0)],
+        })
+        .collect();
+    Ok(SensitivityGrid {
+        interest_rates,
+        clearance_rates,
+        cells,
+    })
+}
+
+/// Estimates the early repayment penalty with the lost interest method (Aktiv-Passiv-Methode):
+/// the payments the bank loses until the end of the fixed-interest period and the remaining
+/// debt then, discounted with the reinvestment rate, less the remaining debt repaid early.
+/// The borrower may terminate ten years after the full payout with six months notice
+/// (§ 489 BGB), so no payments after that are lost.
+pub fn early_repayment_penalty(params: &LoanParams, result: &CalcResultOverview) -> Option<EarlyRepaymentResult> {
+    let early = params.early_repayment.as_ref()?;
+    let remaining = result.months.iter()
+        .take_while(|m| m.month <= early.month)
+        .last()
+        .map(|m| m.remaining)
+        .unwrap_or(params.amount.0);
+    let termination_month = result.construction.as_ref().map(|c| c.months).unwrap_or(0) + 10 * 12 + 6;
+    let end = params.fixed_period.as_ref()
+        .map(|f| f.years * 12)
+        .unwrap_or(result.overall.month)
+        .min(termination_month);
+
+    let v = dec!(1) / (dec!(1) + early.reinvestment_rate / dec!(100) / dec!(12));
+    let mut value = dec!(0);
+    let mut last_month = early.month;
+    let mut discount = dec!(1);
+    let mut lost_remaining = remaining;
+    for m in result.months.iter().filter(|m| early.month < m.month && m.month <= end) {
+        discount *= pow(v, (m.month - last_month) as u32)?;
+        last_month = m.month;
+        value += (m.paid_interest + m.cleared_amount - m.grant + m.commitment_interest) * discount;
+        lost_remaining = m.remaining;
+    }
+    value += lost_remaining * discount;
+
+    Some(EarlyRepaymentResult {
+        month: early.month,
+        date: params.due_date(early.month),
+        remaining,
+        penalty: params.round(value - remaining).max(dec!(0)),
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationParams {
+    pub model: RateModel,
+    /// Standard deviation of the follow-up rate in percentage points, per year for `RateModel::RandomWalk`.
+    pub volatility: Decimal,
+    pub runs: u32,
+    pub seed: u64,
+}
+
+/// 10th, 50th and 90th percentile of a simulated value.
+#[derive(Default, Debug, Clone)]
+pub struct Percentiles<T> {
+    pub p10: T,
+    pub p50: T,
+    pub p90: T,
+}
+
+fn percentiles<T: Clone>(sorted: &[T]) -> Percentiles<T> {
+    let at = |p: usize| sorted[(sorted.len() - 1) * p / 100].clone();
+    Percentiles {
+        p10: at(10),
+        p50: at(50),
+        p90: at(90),
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct SimulationResult {
+    pub runs: u32,
+    pub follow_up_rate: Percentiles<Decimal>,
+    pub paid_interest: Percentiles<Decimal>,
+    pub follow_up_monthly_rate: Percentiles<Decimal>,
+    /// Payoff month, `None` if the loan is not paid off within the runtime.
+    pub payoff_month: Percentiles<Option<i32>>,
+    pub payoff_date: Percentiles<Option<NaiveDate>>,
+}
+
+/// Runs the schedule of an annuity loan with randomly drawn follow-up rates after the
+/// fixed-interest period. Negative rates are floored at zero.
+pub fn simulate(params: &LoanParams, simulation: &SimulationParams) -> Result<SimulationResult, CalcErr> {
+    params.validate()?;
+    let fixed = params.fixed_period.as_ref().ok_or(CalcErr::FixedPeriodMissing)?;
+    let volatility = simulation.volatility.to_f64().unwrap_or(0.0).max(0.0);
+    let current_rate = params.interest_rate.0.to_f64().unwrap_or(0.0);
+    let mut rng = StdRng::seed_from_u64(simulation.seed);
+    let draw = |rng: &mut StdRng| -> f64 {
+        match simulation.model {
+            RateModel::Normal => {
+                let mean = fixed.follow_up_rate.and_then(|r| r.to_f64()).unwrap_or(current_rate);
+                Normal::new(mean, volatility).map(|n| n.sample(rng)).unwrap_or(mean)
+            }
+            RateModel::RandomWalk => {
+                let step = Normal::new(0.0, volatility / 12f64.sqrt()).ok();
+                (0..fixed.years * 12).fold(current_rate, |rate, _| {
+                    rate + step.map(|s| s.sample(rng)).unwrap_or(0.0)
+                })
+            }
+        }
+    };
+
+    let runs = simulation.runs.max(1);
+    let mut follow_up_rates = vec![];
+    let mut paid_interest = vec![];
+    let mut follow_up_monthly_rates = vec![];
+    let mut payoff_months = vec![];
+    for _ in 0..runs {
+        let follow_up_rate = Decimal::from_f64(draw(&mut rng).max(0.0)).unwrap_or_default().round_dp(2);
+        let result = calc_annuity(&LoanParams {
+            fixed_period: Some(FixedPeriod {
+                follow_up_rate: Some(follow_up_rate),
+                ..fixed.clone()
+            }),
+            ..params.clone()
+        })?;
+        follow_up_rates.push(follow_up_rate);
+        paid_interest.push(result.overall.paid_interest);
+        follow_up_monthly_rates.push(result.follow_up_monthly_rate.unwrap_or(result.monthly_rate));
+        payoff_months.push(result.payoff_month);
+    }
+    follow_up_rates.sort();
+    paid_interest.sort();
+    follow_up_monthly_rates.sort();
+    payoff_months.sort_by_key(|m| m.unwrap_or(i32::MAX));
+    let payoff_month = percentiles(&payoff_months);
+    let payoff_date = Percentiles {
+        p10: payoff_month.p10.and_then(|m| params.due_date(m)),
+        p50: payoff_month.p50.and_then(|m| params.due_date(m)),
+        p90: payoff_month.p90.and_then(|m| params.due_date(m)),
+    };
+    Ok(SimulationResult {
+        runs,
+        follow_up_rate: percentiles(&follow_up_rates),
+        paid_interest: percentiles(&paid_interest),
+        follow_up_monthly_rate: percentiles(&follow_up_monthly_rates),
+        payoff_month,
+        payoff_date,
+    })
+}
+
+/// `base^exp`, `None` if it exceeds the range of `Decimal`.
+fn pow(base: Decimal, exp: u32) -> Option<Decimal> {
+    let mut result = dec!(1);
+    let mut base = base;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.checked_mul(base)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = base.checked_mul(base)?;
+        }
+    }
+    Some(result)
+}
+
+/// Payment per period that pays off `amount` in exactly `periods` periods with
+/// `period_rate` given as fraction.
+pub fn annuity_payment(amount: Decimal, period_rate: Decimal, periods: u32, timing: &InterestTiming) -> Result<Decimal, CalcErr> {
+    if periods == 0 {
+        return Err(CalcErr::RuntimeZero);
+    }
+    if period_rate == dec!(0) {
+        return Ok(amount / Decimal::from(periods));
+    }
+    let discount = match timing {
+        InterestTiming::InArrears => dec!(1) / pow(dec!(1) + period_rate, periods).ok_or(CalcErr::Overflow)?,
+        InterestTiming::InAdvance => pow(dec!(1) - period_rate, periods).ok_or(CalcErr::Overflow)?,
+    };
+    amount.checked_mul(period_rate)
+        .and_then(|a| a.checked_div(dec!(1) - discount))
+        .ok_or(CalcErr::Overflow)
+}
+
+/// Runs the savings phase of a building savings contract until allotment and the
+/// building loan for the rest of the contract sum (`amount`) afterwards. The loan is
+/// charged `interest_rate` and repaid with `clearance_rate`.
+pub fn calc_building_saving(params: &LoanParams) -> CalcResultOverview {
+    let mut result = CalcResultOverview::new(params.amount.0);
+    let savings = &params.building_savings;
+
+    let contract_sum = params.amount.0;
+    let interest_rate = params.interest_rate.0 / dec!(100);
+    let clearance_rate = params.clearance_rate.0 / dec!(100);
+    let deposit_rate = savings.deposit_rate / dec!(100);
+    let minimum_balance = contract_sum * savings.minimum_balance / dec!(100);
+    result.clearance_rate = params.clearance_rate.0;
+
+    let mut balance = dec!(0);
+    let mut accrued_interest = dec!(0);
+    let mut balance_sum = dec!(0);
+    let mut allotment: Option<BuildingSavingsResult> = None;
+    let mut remaining = contract_sum;
+    for month in 1..=params.runtime.months() {
+        if let Some(allotment) = &allotment {
+            let paid_interest_month = params.round(remaining * interest_rate / dec!(12));
+
+            let mut cleared_month = allotment.loan_monthly_rate - paid_interest_month;
+            if pays_off(remaining, cleared_month) {
+                cleared_month = remaining;
+                result.payoff_month = Some(month);
+            }
+            remaining -= cleared_month;
+
+            result.push_month(CalcResult {
+                month,
+                remaining,
+                payment: paid_interest_month + cleared_month,
+                paid_interest: paid_interest_month,
+                cleared_amount: cleared_month,
+                ..CalcResult::default()
+            });
+            if result.payoff_month.is_some() {
+                break;
+            }
+            continue;
+        }
+
+        let fees = if month == 1 {
+            params.round(contract_sum * savings.contract_fee / dec!(100))
+        }else {
+            dec!(0)
+        };
+        balance += savings.monthly_deposit - fees;
+        balance_sum += balance;
+        if balance > dec!(0) {
+            accrued_interest += balance * deposit_rate / dec!(12);
+        }
+
+        let rating = if contract_sum > dec!(0) { balance_sum / contract_sum } else { dec!(0) };
+        let allotted = balance + accrued_interest >= minimum_balance
+            && savings.minimum_rating.map(|r| rating >= r).unwrap_or(true);
+
+        let mut earned_interest = dec!(0);
+        if month % 12 == 0 || allotted {
+            earned_interest = params.round(accrued_interest);
+            balance += earned_interest;
+            accrued_interest = dec!(0);
+        }
+        let saved_month = savings.monthly_deposit - fees + earned_interest;
+        remaining -= saved_month;
+
+        if allotted {
+            let loan_amount = remaining.max(dec!(0));
+            allotment = Some(BuildingSavingsResult {
+                allotment_month: month,
+                balance,
+                loan_amount,
+                loan_monthly_rate: params.round(loan_amount * (interest_rate + clearance_rate) / dec!(12)),
+            });
+            if loan_amount == dec!(0) {
+                result.payoff_month = Some(month);
+            }
+        }
+
+        result.push_month(CalcResult {
+            month,
+            remaining,
+            payment: savings.monthly_deposit,
+            cleared_amount: saved_month,
+            fees,
+            earned_interest,
+            savings_balance: if allotted { dec!(0) } else { balance },
+            ..CalcResult::default()
+        });
+        if result.payoff_month.is_some() {
+            break;
+        }
+    }
+
+    result.monthly_rate = allotment.as_ref().map(|a| a.loan_monthly_rate).unwrap_or(dec!(0));
+    result.building_savings = allotment;
+    result
+}
+
+/// Pays off `amount` with a constant payment. A construction loan pays only interest on the
+/// drawn amount and commitment interest on the rest until the last tranche is paid out.
+pub fn calc_annuity(params: &LoanParams) -> Result<CalcResultOverview, CalcErr> {
+    params.validate_special_repayments()?;
+    params.validate_disbursements()?;
+    let mut result = CalcResultOverview::new(params.amount.0);
+
+    let amount = params.amount.0;
+    let interest_rate = params.interest_rate.0 / dec!(100);
+    let clearance_rate = params.clearance_rate.0 / dec!(100);
+    let step = params.payment_frequency.months();
+    let per_year = params.payment_frequency.per_year();
+    let periods = params.periods();
+    // Construction lasts whole payment periods.
+    let construction_months = (params.construction_months() + step - 1) / step * step;
+    let repayment_free_months = params.repayment_free_months.max(construction_months).max(0);
+    let repayment_free_periods = repayment_free_months / step;
+    match params.annuity_mode {
+        AnnuityMode::ClearanceRate => {
+            result.monthly_rate = params.round(amount * (interest_rate + clearance_rate ) / per_year);
+            result.clearance_rate = params.clearance_rate.0;
+        }
+        AnnuityMode::Runtime => {
+            result.monthly_rate = params.round(params.dated_annuity_payment(amount, interest_rate, repayment_free_periods + 1, periods)?);
+            if amount != dec!(0) {
+                result.clearance_rate = result.monthly_rate * per_year / amount * dec!(100) - params.interest_rate.0;
+            }
+        }
+    }
+
+    if repayment_free_periods > 0 {
+        result.repayment_free_monthly_rate = Some(params.round(amount * params.regular_period_rate(interest_rate)));
+    }
+
+    let fixed_months = params.fixed_period.as_ref().map(|f| f.years * 12);
+    let mut interest_rate = interest_rate;
+    let mut monthly_rate = result.monthly_rate;
+    // Whether the payment is solved to pay off the loan at the end of the runtime, the last
+    // payment then clears what rounding the payment to cents left over.
+    let mut pays_off_at_end = matches!(params.annuity_mode, AnnuityMode::Runtime);
+    let mut remaining = if params.disbursements.is_empty() { amount } else { dec!(0) };
+    let mut construction = ConstructionResult {
+        months: construction_months,
+        ..ConstructionResult::default()
+    };
+    for period in 1..=periods {
+        let month = period * step;
+        let first_month = month - step + 1;
+        if let (Some(fixed), Some(fixed_months)) = (&params.fixed_period, fixed_months) {
+            if first_month <= fixed_months + 1 && fixed_months < month {
+                let follow_up_rate = fixed.follow_up_rate.unwrap_or(params.interest_rate.0);
+                interest_rate = follow_up_rate / dec!(100);
+                if fixed.follow_up_clearance_rate.is_some() {
+                    pays_off_at_end = false;
+                }
+                monthly_rate = params.round(match (&fixed.follow_up_clearance_rate, &params.annuity_mode) {
+                    (Some(clearance_rate), _) => remaining * (interest_rate + clearance_rate / dec!(100)) / per_year,
+                    (None, AnnuityMode::Runtime) => params.dated_annuity_payment(remaining, interest_rate, period, periods)?,
+                    (None, AnnuityMode::ClearanceRate) => monthly_rate,
+                });
+                result.follow_up_monthly_rate = Some(monthly_rate);
+            }
+        }
+        if let Some(change) = params.rate_change(first_month, month) {
+            interest_rate = change.interest_rate / dec!(100);
+            if matches!(params.rate_change_mode, RateChangeMode::KeepPayment) {
+                pays_off_at_end = false;
+            }
+            monthly_rate = params.round(match (&params.rate_change_mode, &params.annuity_mode) {
+                (RateChangeMode::KeepPayment, _) => monthly_rate,
+                (RateChangeMode::RecomputePayment, AnnuityMode::Runtime) => params.dated_annuity_payment(remaining, interest_rate, period, periods)?,
+                (RateChangeMode::RecomputePayment, AnnuityMode::ClearanceRate) => remaining * (interest_rate + clearance_rate) / per_year,
+            });
+        }
+
+        let rate = params.period_rate(interest_rate, first_month, month);
+        let mut commitment_month = dec!(0);
+        let (mut paid_interest_month, mut cleared_month) = if month <= construction_months {
+            let mut interest = dec!(0);
+            for m in first_month..=month {
+                remaining += params.disbursed(m);
+                interest += remaining * params.period_rate(interest_rate, m, m);
+                if m > params.commitment_free_months {
+                    commitment_month += (amount - remaining) * params.period_rate(params.commitment_rate / dec!(100), m, m);
+                }
+            }
+            commitment_month = params.round(commitment_month);
+            construction.interest += params.round(interest);
+            construction.commitment_interest += commitment_month;
+            (params.round(interest), dec!(0))
+        }else if month <= repayment_free_months {
+            (params.interest(remaining, dec!(0), rate), dec!(0))
+        }else {
+            params.split_payment(remaining, monthly_rate, rate)
+        };
+        let mut special_month = dec!(0);
+        let mut grant_month = dec!(0);
+        if month > construction_months {
+            special_month = (first_month..=month)
+                .map(|m| params.round(params.special_repayment(m)))
+                .fold(dec!(0), |acc, s| acc + s);
+            // A grant due during the construction phase is credited in the first period after it.
+            let grant_first_month = if first_month <= construction_months + 1 { 1 } else { first_month };
+            grant_month = params.repayment_grant(grant_first_month, month, remaining - cleared_month - special_month);
+            let residual = remaining - cleared_month - special_month - grant_month;
+            if period == periods && ((pays_off_at_end && is_solved_rounding_difference(residual, params.regular_period_rate(interest_rate), periods))
+                || is_rounding_difference(residual, periods)) {
+                cleared_month = remaining - special_month - grant_month;
+            }
+            if pays_off(remaining, cleared_month) {
+                cleared_month = remaining;
+                paid_interest_month = params.interest(remaining, cleared_month, rate);
+                special_month = dec!(0);
+                grant_month = dec!(0);
+                result.payoff_month = Some(month);
+            }else if pays_off(remaining - cleared_month, special_month) {
+                special_month = remaining - cleared_month;
+                grant_month = dec!(0);
+                result.payoff_month = Some(month);
+            }else if pays_off(remaining - cleared_month - special_month, grant_month) {
+                result.payoff_month = Some(month);
+            }
+        }
+        remaining -= cleared_month + special_month + grant_month;
+
+        result.push_month(CalcResult {
+            month,
+            remaining,
+            payment: paid_interest_month + cleared_month + special_month + commitment_month,
+            paid_interest: paid_interest_month,
+            cleared_amount: cleared_month + special_month + grant_month,
+            special_repayment: special_month,
+            commitment_interest: commitment_month,
+            grant: grant_month,
+            ..CalcResult::default()
+        });
+        if let Some(fixed_months) = fixed_months {
+            if (first_month <= fixed_months && fixed_months <= month) || (result.payoff_month.is_some() && month < fixed_months) {
+                result.fixed_period_remaining = Some(remaining);
+            }
+        }
+        if result.payoff_month.is_some() {
+            break;
+        }
+    }
+    if construction_months > 0 {
+        result.construction = Some(construction);
+    }
+
+    if !params.special_repayments.is_empty() {
+        let plain_params = LoanParams {
+            special_repayments: vec![],
+            ..params.clone()
+        };
+        let plain = calc_annuity(&plain_params)?;
+        // Without special repayments the loan may be paid off only after the runtime.
+        let plain_payoff_month = match (plain.payoff_month, &params.annuity_mode) {
+            (None, AnnuityMode::ClearanceRate) => calc_annuity(&LoanParams {
+                runtime: Duration::from_years(Decimal::from(MAX_RUNTIME_YEARS)),
+                ..plain_params
+            }).ok().and_then(|r| r.payoff_month),
+            (payoff_month, _) => payoff_month,
+        };
+        result.special_repayment_savings = Some(SpecialRepaymentSavings {
+            interest: plain.overall.paid_interest - result.overall.paid_interest,
+            months: result.payoff_month.and_then(|month| plain_payoff_month.map(|plain| plain - month)),
+        });
+    }
+    Ok(result)
+}
+
+/// Repays the same principal share every period so the loan is paid off at the end of the
+/// runtime, the payment falls with the interest.
+pub fn calc_linear(params: &LoanParams) -> Result<CalcResultOverview, CalcErr> {
+    let mut result = CalcResultOverview::new(params.amount.0);
+
+    let amount = params.amount.0;
+    let step = params.payment_frequency.months();
+    let periods = params.periods();
+    let repayment_free_months = params.repayment_free_months.max(0);
+    let repayment_free_periods = repayment_free_months / step;
+    let repayment_periods = periods - repayment_free_periods;
+    if repayment_periods <= 0 {
+        return Err(CalcErr::RuntimeZero);
+    }
+    let cleared = params.round(amount / Decimal::from(repayment_periods));
+    if amount != dec!(0) {
+        result.clearance_rate = cleared * params.payment_frequency.per_year() / amount * dec!(100);
+    }
+    if repayment_free_periods > 0 {
+        result.repayment_free_monthly_rate = Some(params.round(amount * params.regular_period_rate(params.interest_rate.0 / dec!(100))));
+    }
+
+    let mut interest_rate = params.interest_rate.0 / dec!(100);
+    let mut remaining = amount;
+    for period in 1..=periods {
+        let month = period * step;
+        let first_month = month - step + 1;
+        if let Some(change) = params.rate_change(first_month, month) {
+            interest_rate = change.interest_rate / dec!(100);
+        }
+        let rate = params.period_rate(interest_rate, first_month, month);
+
+        let mut cleared_month = if month <= repayment_free_months {
+            dec!(0)
+        }else {
+            cleared
+        };
+        let mut grant_month = params.repayment_grant(first_month, month, remaining - cleared_month);
+        if period == periods && is_rounding_difference(remaining - cleared_month - grant_month, periods) {
+            cleared_month = remaining - grant_month;
+        }
+        if pays_off(remaining, cleared_month) {
+            cleared_month = remaining;
+            grant_month = dec!(0);
+            result.payoff_month = Some(month);
+        }else if pays_off(remaining - cleared_month, grant_month) {
+            result.payoff_month = Some(month);
+        }
+        let paid_interest_month = params.interest(remaining, cleared_month, rate);
+        remaining -= cleared_month + grant_month;
+
+        if period == repayment_free_periods + 1 {
+            result.monthly_rate = paid_interest_month + cleared_month;
+        }
+        result.push_month(CalcResult {
+            month,
+            remaining,
+            payment: paid_interest_month + cleared_month,
+            paid_interest: paid_interest_month,
+            cleared_amount: cleared_month + grant_month,
+            grant: grant_month,
+            ..CalcResult::default()
+        });
+        if result.payoff_month.is_some() {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+/// Charges interest on the full amount until maturity, when the principal is repaid from
+/// the balance of the repayment vehicle. An uncovered part stays as remaining debt.
+/// The schedule is monthly as contributions to the vehicle are.
+pub fn calc_bullet(params: &LoanParams) -> Result<CalcResultOverview, CalcErr> {
+    let mut result = CalcResultOverview::new(params.amount.0);
+
+    let amount = params.amount.0;
+    let months = params.runtime.months();
+    if months <= 0 {
+        return Err(CalcErr::RuntimeZero);
+    }
+    let step = params.payment_frequency.months();
+    let vehicle = params.repayment_vehicle.as_ref();
+    let contribution = vehicle.map(|v| v.monthly_contribution).unwrap_or(dec!(0));
+    result.monthly_rate = params.round(amount * params.regular_period_rate(params.interest_rate.0 / dec!(100)))
+        + contribution * Decimal::from(step);
+
+    let mut interest_rate = params.interest_rate.0 / dec!(100);
+    let mut balance = dec!(0);
+    let mut accrued_interest = dec!(0);
+    for month in 1..=months {
+        if let Some(change) = params.rate_change(month, month) {
+            interest_rate = change.interest_rate / dec!(100);
+        }
+        // Interest of a whole period is due at its end, or at its start when paid in advance.
+        let paid_interest_month = match params.interest_timing {
+            InterestTiming::InArrears if month % step == 0 => params.round(amount * params.period_rate(interest_rate, month - step + 1, month)),
+            InterestTiming::InAdvance if (month - 1) % step == 0 => params.round(amount * params.period_rate(interest_rate, month, month + step - 1)),
+            _ => dec!(0),
+        };
+
+        let mut fees = dec!(0);
+        let mut earned_interest = dec!(0);
+        if let Some(vehicle) = vehicle {
+            let return_rate = vehicle.return_rate / dec!(100);
+            match vehicle.kind {
+                RepaymentVehicleKind::SavingsPlan => {
+                    fees = params.round(contribution * vehicle.fee / dec!(100));
+                    balance += contribution - fees;
+                    earned_interest = params.round(balance * return_rate / dec!(12));
+                    balance += earned_interest;
+                }
+                RepaymentVehicleKind::BuildingSavings => {
+                    if month == 1 {
+                        fees = params.round(amount * vehicle.fee / dec!(100));
+                    }
+                    balance += contribution - fees;
+                    if balance > dec!(0) {
+                        accrued_interest += balance * return_rate / dec!(12);
+                    }
+                    if month % 12 == 0 || month == months {
+                        earned_interest = params.round(accrued_interest);
+                        balance += earned_interest;
+                        accrued_interest = dec!(0);
+                    }
+                }
+            }
+        }
+
+        let mut cleared_month = dec!(0);
+        if month == months {
+            cleared_month = balance.max(dec!(0)).min(amount);
+            result.maturity = Some(MaturityResult {
+                vehicle_balance: balance,
+                shortfall: amount - cleared_month,
+                surplus: (balance - cleared_month).max(dec!(0)),
+            });
+            balance -= cleared_month;
+            if pays_off(amount, cleared_month) {
+                result.payoff_month = Some(month);
+            }
+        }
+
+        result.push_month(CalcResult {
+            month,
+            remaining: amount - cleared_month,
+            payment: paid_interest_month + contribution,
+            paid_interest: paid_interest_month,
+            cleared_amount: cleared_month,
+            fees,
+            earned_interest,
+            savings_balance: balance,
+            ..CalcResult::default()
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annuity(amount: Decimal, interest_rate: Decimal, runtime_years: i32) -> LoanParams {
+        LoanParams {
+            loan_type: LoanType::Annuity,
+            annuity_mode: AnnuityMode::Runtime,
+            amount: Money(amount),
+            interest_rate: Percent(interest_rate),
+            runtime: Duration::from_years(Decimal::from(runtime_years)),
+            ..LoanParams::default()
+        }
+    }
+
+    fn assert_rows_sum_to_totals(result: &CalcResultOverview) {
+        let sum = |f: fn(&CalcResult) -> Decimal| result.months.iter().map(|m| f(m)).fold(dec!(0), |acc, v| acc + v);
+        assert_eq!(sum(|m| m.payment), result.overall.payment);
+        assert_eq!(sum(|m| m.paid_interest), result.overall.paid_interest);
+        assert_eq!(sum(|m| m.cleared_amount), result.overall.cleared_amount);
+    }
+
+    #[test]
+    fn annuity_payment_and_payoff() {
+        let result = calc(&annuity(dec!(100000), dec!(3), 10)).unwrap();
+        assert_eq!(result.monthly_rate, dec!(965.61));
+        assert_eq!(result.payoff_month, Some(120));
+        assert_eq!(result.overall.remaining, dec!(0));
+
+        let params = LoanParams {
+            annuity_mode: AnnuityMode::ClearanceRate,
+            clearance_rate: Percent(dec!(2)),
+            ..annuity(dec!(100000), dec!(3), 10)
+        };
+        let result = calc(&params).unwrap();
+        assert_eq!(result.monthly_rate, dec!(416.67));
+        assert_eq!(result.payoff_month, None);
+        assert!(result.overall.remaining > dec!(0));
+
+        let params = LoanParams {
+            clearance_rate: Percent(dec!(10)),
+            runtime: Duration::from_years(dec!(30)),
+            ..params
+        };
+        let result = calc(&params).unwrap();
+        let last = result.months.last().unwrap();
+        assert!(result.payoff_month.unwrap() < 360);
+        assert_eq!(result.overall.remaining, dec!(0));
+        assert!(last.payment < result.monthly_rate);
+    }
+
+    #[test]
+    fn rows_sum_to_totals() {
+        for rounding_mode in &[RoundingMode::HalfUp, RoundingMode::HalfEven, RoundingMode::Down, RoundingMode::Up] {
+            let params = LoanParams {
+                rounding_mode: rounding_mode.clone(),
+                fees: LoanFees {
+                    account_fee: dec!(1.5),
+                    ..LoanFees::default()
+                },
+                ..annuity(dec!(100000), dec!(3), 10)
+            };
+            let result = calc(&params).unwrap();
+            assert_rows_sum_to_totals(&result);
+            assert_eq!(result.overall.cleared_amount, dec!(100000));
+        }
+    }
+
+    #[test]
+    fn interest_in_advance() {
+        let params = LoanParams {
+            interest_timing: InterestTiming::InAdvance,
+            ..annuity(dec!(100000), dec!(3), 10)
+        };
+        let result = calc(&params).unwrap();
+        let first = &result.months[0];
+        assert_eq!(result.monthly_rate, dec!(963.54));
+        assert_eq!(first.remaining, dec!(99284.67));
+        // Interest on the debt left after the month's clearance.
+        assert_eq!(first.paid_interest, dec!(248.21));
+        assert_eq!(result.payoff_month, Some(120));
+        assert_rows_sum_to_totals(&result);
+    }
+
+    #[test]
+    fn effective_rate_with_fees() {
+        let params = annuity(dec!(100000), dec!(3), 10);
+        assert_eq!(calc(&params).unwrap().effective_rate.unwrap().round_dp(2), dec!(3.04));
+
+        let params = LoanParams {
+            fees: LoanFees {
+                disagio: dec!(2),
+                account_fee: dec!(5),
+                ..LoanFees::default()
+            },
+            ..params
+        };
+        let result = calc(&params).unwrap();
+        assert_eq!(result.monthly_rate, dec!(970.61));
+        assert_eq!(result.effective_rate.unwrap().round_dp(2), dec!(3.59));
+    }
+
+    #[test]
+    fn construction_tranches() {
+        let params = LoanParams {
+            disbursements: vec![
+                Disbursement { month: 1, amount: dec!(40000) },
+                Disbursement { month: 4, amount: dec!(30000) },
+                Disbursement { month: 7, amount: dec!(30000) },
+            ],
+            commitment_rate: dec!(3),
+            commitment_free_months: 3,
+            ..annuity(dec!(100000), dec!(3), 20)
+        };
+        let result = calc(&params).unwrap();
+        let construction = result.construction.as_ref().unwrap();
+        assert_eq!(construction.months, 7);
+        assert_eq!(construction.interest, dec!(1075.00));
+        assert_eq!(construction.commitment_interest, dec!(225.00));
+        // Interest on the drawn amount, commitment interest on the rest after the free months.
+        assert_eq!(result.months[0].paid_interest, dec!(100.00));
+        assert_eq!(result.months[0].commitment_interest, dec!(0));
+        assert_eq!(result.months[3].paid_interest, dec!(175.00));
+        assert_eq!(result.months[3].commitment_interest, dec!(75.00));
+        assert_eq!(result.months[6].cleared_amount, dec!(0));
+        assert!(result.months[7].cleared_amount > dec!(0));
+        assert_eq!(result.overall.remaining, dec!(0));
+        assert_rows_sum_to_totals(&result);
+
+        let params = LoanParams {
+            disbursements: params.disbursements[..2].to_vec(),
+            ..params
+        };
+        assert!(matches!(calc(&params), Err(CalcErr::DisbursementMismatch)));
+    }
+
+    #[test]
+    fn special_repayments_save_months_beyond_runtime() {
+        let params = LoanParams {
+            annuity_mode: AnnuityMode::ClearanceRate,
+            clearance_rate: Percent(dec!(2)),
+            special_repayments: vec![SpecialRepayment { month: 12, amount: SpecialAmount::PercentOfPrincipal(dec!(5)), yearly: true }],
+            ..annuity(dec!(100000), dec!(3), 15)
+        };
+        let result = calc(&params).unwrap();
+        let plain = calc(&LoanParams {
+            special_repayments: vec![],
+            runtime: Duration::from_years(dec!(40)),
+            ..params.clone()
+        }).unwrap();
+        let savings = result.special_repayment_savings.unwrap();
+        assert!(plain.payoff_month.unwrap() > 180);
+        assert_eq!(savings.months, Some(plain.payoff_month.unwrap() - result.payoff_month.unwrap()));
+
+        let params = LoanParams {
+            runtime: Duration::from_years(dec!(5)),
+            ..params
+        };
+        assert_eq!(calc(&params).unwrap().special_repayment_savings.unwrap().months, None);
+    }
+
+    #[test]
+    fn early_repayment_before_start_is_invalid() {
+        for &month in &[0, -5] {
+            let params = LoanParams {
+                early_repayment: Some(EarlyRepayment { month, reinvestment_rate: dec!(1) }),
+                ..annuity(dec!(100000), dec!(3), 10)
+            };
+            match calc(&params) {
+                Err(CalcErr::InvalidParams(invalid)) => assert!(matches!(invalid.as_slice(), [InvalidParam { param: Param::EarlyRepayment, reason: InvalidReason::BeforeStart }])),
+                result => panic!("month {} accepted: {:?}", month, result.map(|r| r.early_repayment)),
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_period_switches_to_follow_up_rate() {
+        let params = LoanParams {
+            fixed_period: Some(FixedPeriod { years: 10, follow_up_rate: Some(dec!(5)), follow_up_clearance_rate: None }),
+            ..annuity(dec!(100000), dec!(3), 25)
+        };
+        let result = calc(&params).unwrap();
+        let end_of_fixed = &result.months[119];
+        let first_follow_up = &result.months[120];
+        assert_eq!(result.fixed_period_remaining, Some(end_of_fixed.remaining));
+        assert_eq!(first_follow_up.paid_interest, (end_of_fixed.remaining * dec!(0.05) / dec!(12)).round_dp(2));
+        // The payment is solved again for the rest of the runtime at the follow-up rate.
+        assert!(result.follow_up_monthly_rate.unwrap() > result.monthly_rate);
+        assert_eq!(first_follow_up.payment, result.follow_up_monthly_rate.unwrap());
+        assert_eq!(result.payoff_month, Some(300));
+
+        let params = LoanParams {
+            annuity_mode: AnnuityMode::ClearanceRate,
+            clearance_rate: Percent(dec!(2)),
+            ..params
+        };
+        let result = calc(&params).unwrap();
+        assert_eq!(result.months[120].payment, result.monthly_rate);
+    }
+
+    #[test]
+    fn fixed_period_longer_than_runtime_is_invalid() {
+        for &years in &[26, 300_000_000] {
+            let params = LoanParams {
+                fixed_period: Some(FixedPeriod { years, follow_up_rate: None, follow_up_clearance_rate: None }),
+                ..annuity(dec!(100000), dec!(3), 25)
+            };
+            match calc(&params) {
+                Err(CalcErr::InvalidParams(invalid)) => assert!(matches!(invalid.as_slice(), [InvalidParam { param: Param::FixedPeriod, reason: InvalidReason::TooLong }])),
+                result => panic!("{} years accepted: {:?}", years, result.map(|r| r.payoff_month)),
+            }
+        }
+    }
+
+    #[test]
+    fn special_repayment_not_positive_is_invalid() {
+        for (month, amount, reason) in &[
+            (12, SpecialAmount::Fixed(dec!(-50000)), InvalidReason::Negative),
+            (12, SpecialAmount::PercentOfPrincipal(dec!(0)), InvalidReason::Zero),
+            (0, SpecialAmount::Fixed(dec!(1000)), InvalidReason::BeforeStart),
+        ] {
+            let params = LoanParams {
+                special_repayments: vec![SpecialRepayment { month: *month, amount: amount.clone(), yearly: false }],
+                ..annuity(dec!(100000), dec!(3), 10)
+            };
+            match calc(&params) {
+                Err(CalcErr::InvalidParams(invalid)) => {
+                    assert_eq!(invalid.len(), 1);
+                    assert!(matches!(invalid[0].param, Param::SpecialRepayments));
+                    assert_eq!(&invalid[0].reason, reason);
+                }
+                result => panic!("month {} accepted: {:?}", month, result.map(|r| r.overall.remaining)),
+            }
+        }
+    }
+
+    #[test]
+    fn runtime_pays_off_at_end() {
+        for amount in (100_000..100_100).step_by(13).chain(vec![100_049, 987_654]) {
+            for &interest_rate in &[dec!(1), dec!(3.75), dec!(5), dec!(10)] {
+                for &runtime_years in &[5, 30] {
+                    let result = calc(&annuity(Decimal::from(amount), interest_rate, runtime_years)).unwrap();
+                    assert_eq!(result.overall.remaining, dec!(0), "{} at {} % over {} years", amount, interest_rate, runtime_years);
+                    assert_eq!(result.payoff_month, Some(runtime_years * 12), "{} at {} % over {} years", amount, interest_rate, runtime_years);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn runtime_solved_on_dated_schedule() {
+        for day_count in &[DayCount::Thirty360, DayCount::Act360, DayCount::Act365] {
+            let params = LoanParams {
+                start_date: NaiveDate::from_ymd_opt(2020, 1, 15),
+                day_count: day_count.clone(),
+                ..annuity(dec!(100000), dec!(5), 30)
+            };
+            let result = calc(&params).unwrap();
+            let last = result.months.last().unwrap();
+            assert_eq!(result.payoff_month, Some(360));
+            assert_eq!(result.overall.remaining, dec!(0));
+            assert!((last.payment - result.monthly_rate).abs() < dec!(5), "last payment {} of {}", last.payment, result.monthly_rate);
+        }
+    }
+
+    #[test]
+    fn bullet_repaid_from_savings_plan() {
+        let params = LoanParams {
+            loan_type: LoanType::Bullet,
+            repayment_vehicle: Some(RepaymentVehicle {
+                kind: RepaymentVehicleKind::SavingsPlan,
+                monthly_contribution: dec!(800),
+                return_rate: dec!(0),
+                fee: dec!(0),
+            }),
+            ..annuity(dec!(100000), dec!(3), 10)
+        };
+        let result = calc(&params).unwrap();
+        assert_eq!(result.monthly_rate, dec!(1050));
+        assert_eq!(result.months[0].savings_balance, dec!(800));
+        assert_eq!(result.overall.paid_interest, dec!(30000));
+        let maturity = result.maturity.as_ref().unwrap();
+        assert_eq!(maturity.vehicle_balance, dec!(96000));
+        assert_eq!(maturity.shortfall, dec!(4000));
+        assert_eq!(result.overall.remaining, dec!(4000));
+        assert_eq!(result.payoff_month, None);
+        assert_rows_sum_to_totals(&result);
+
+        let params = LoanParams {
+            repayment_vehicle: Some(RepaymentVehicle {
+                monthly_contribution: dec!(900),
+                return_rate: dec!(2),
+                ..params.repayment_vehicle.clone().unwrap()
+            }),
+            ..params
+        };
+        let result = calc(&params).unwrap();
+        let maturity = result.maturity.as_ref().unwrap();
+        assert!(maturity.surplus > dec!(0));
+        assert_eq!(maturity.shortfall, dec!(0));
+        assert_eq!(result.overall.remaining, dec!(0));
+        assert_eq!(result.payoff_month, Some(120));
+    }
+
+    #[test]
+    fn simulation_without_volatility_uses_follow_up_rate() {
+        let params = LoanParams {
+            fixed_period: Some(FixedPeriod {
+                years: 10,
+                follow_up_rate: Some(dec!(4)),
+                follow_up_clearance_rate: None,
+            }),
+            ..annuity(dec!(100000), dec!(3), 25)
+        };
+        let simulation = SimulationParams {
+            model: RateModel::Normal,
+            volatility: dec!(0),
+            runs: 20,
+            seed: 1,
+        };
+        let result = simulate(&params, &simulation).unwrap();
+        let expected = calc(&params).unwrap();
+        assert_eq!(result.runs, 20);
+        assert_eq!(result.follow_up_rate.p10, dec!(4));
+        assert_eq!(result.follow_up_rate.p90, dec!(4));
+        assert_eq!(result.paid_interest.p50, expected.overall.paid_interest);
+        assert_eq!(result.payoff_month.p50, expected.payoff_month);
+
+        let volatile = simulate(&params, &SimulationParams { volatility: dec!(1), ..simulation.clone() }).unwrap();
+        assert!(volatile.follow_up_rate.p10 < volatile.follow_up_rate.p90);
+        assert!(volatile.paid_interest.p10 < volatile.paid_interest.p90);
+
+        match simulate(&LoanParams { fixed_period: None, ..params.clone() }, &simulation) {
+            Err(CalcErr::FixedPeriodMissing) => (),
+            r => panic!("unexpected {:?}", r),
+        }
+        match simulate(&LoanParams { interest_rate: Percent(dec!(-1)), ..params }, &simulation) {
+            Err(CalcErr::InvalidParams(invalid)) => assert_eq!(invalid[0].param, Param::InterestRate),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn max_amount_fits_budget() {
+        let params = LoanParams {
+            annuity_mode: AnnuityMode::ClearanceRate,
+            ..annuity(dec!(0), dec!(3), 10)
+        };
+        for &target_remaining in &[dec!(0), dec!(20000)] {
+            let max = max_amount(&params, dec!(965.61), target_remaining).unwrap();
+            let result = calc(&LoanParams {
+                amount: Money(max.amount),
+                clearance_rate: Percent(max.clearance_rate),
+                ..params.clone()
+            }).unwrap();
+            assert!(result.monthly_rate <= dec!(965.61), "{} for {}", result.monthly_rate, max.amount);
+            assert!(result.overall.remaining <= target_remaining + dec!(1), "{} left of {}", result.overall.remaining, max.amount);
+        }
+        let max = max_amount(&params, dec!(965.61), dec!(0)).unwrap();
+        assert!((max.amount - dec!(100000)).abs() < dec!(1), "{}", max.amount);
+
+        match max_amount(&params, Decimal::from(i64::MAX) * dec!(1000000000), dec!(0)) {
+            Err(CalcErr::Overflow) => (),
+            r => panic!("unexpected {:?}", r),
+        }
+        match max_amount(&params, dec!(0), dec!(0)) {
+            Err(CalcErr::GoalNotReachable) => (),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn required_clearance_rate_reaches_target() {
+        let params = LoanParams {
+            annuity_mode: AnnuityMode::ClearanceRate,
+            ..annuity(dec!(100000), dec!(3), 10)
+        };
+        let remaining = |clearance_rate: Decimal| calc(&LoanParams {
+            clearance_rate: Percent(clearance_rate),
+            ..params.clone()
+        }).unwrap().overall.remaining;
+        for &target_remaining in &[dec!(0), dec!(50000)] {
+            let clearance_rate = required_clearance_rate(&params, target_remaining).unwrap();
+            assert!(remaining(clearance_rate) <= target_remaining);
+            assert!(remaining(clearance_rate - dec!(0.01)) > target_remaining);
+        }
+        assert_eq!(required_clearance_rate(&params, dec!(100000)).unwrap(), dec!(0));
+    }
+
+    #[test]
+    fn early_repayment_penalty_by_reinvestment_rate() {
+        let penalty = |month: i32, reinvestment_rate: Decimal| {
+            let params = LoanParams {
+                fixed_period: Some(FixedPeriod {
+                    years: 15,
+                    follow_up_rate: None,
+                    follow_up_clearance_rate: None,
+                }),
+                early_repayment: Some(EarlyRepayment {
+                    month,
+                    reinvestment_rate,
+                }),
+                ..annuity(dec!(100000), dec!(4), 25)
+            };
+            calc(&params).unwrap().early_repayment.unwrap()
+        };
+        // Discounted with the loan rate the lost payments are worth the remaining debt.
+        assert!(penalty(24, dec!(4)).penalty < dec!(1));
+        let low = penalty(24, dec!(1));
+        assert!(low.penalty > penalty(24, dec!(2)).penalty);
+        assert!(low.penalty > dec!(10000), "{}", low.penalty);
+        assert_eq!(low.remaining, calc(&annuity(dec!(100000), dec!(4), 25)).unwrap().months[23].remaining);
+        // Terminable without penalty ten and a half years after the payout.
+        assert_eq!(penalty(126, dec!(1)).penalty, dec!(0));
+    }
+
+    #[test]
+    fn month_at_due_date() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let params = LoanParams {
+            start_date: Some(date(2020, 1, 15)),
+            ..LoanParams::default()
+        };
+        assert_eq!(params.month_at(date(2021, 1, 15)), Some(12));
+        assert_eq!(params.month_at(date(2021, 1, 14)), Some(11));
+        assert_eq!(params.month_at(date(2020, 2, 10)), Some(1));
+        assert_eq!(params.month_at(date(2019, 12, 31)), Some(0));
+        let params = LoanParams {
+            payment_day: Some(28),
+            ..params
+        };
+        assert_eq!(params.month_at(date(2021, 1, 27)), Some(11));
+        assert_eq!(params.month_at(date(2021, 2, 28)), Some(13));
+        assert_eq!(LoanParams::default().month_at(date(2021, 1, 15)), None);
+    }
+
+    #[test]
+    fn repayment_grant_lowers_debt() {
+        let params = LoanParams {
+            annuity_mode: AnnuityMode::ClearanceRate,
+            clearance_rate: Percent(dec!(2)),
+            ..annuity(dec!(100000), dec!(3), 40)
+        };
+        let plain = calc(&params).unwrap();
+        let params = LoanParams {
+            repayment_grant: Some(RepaymentGrant {
+                month: 12,
+                amount: SpecialAmount::PercentOfPrincipal(dec!(10)),
+            }),
+            ..params
+        };
+        let result = calc(&params).unwrap();
+        assert_eq!(result.months[11].grant, dec!(10000));
+        assert_eq!(result.months[11].payment, plain.months[11].payment);
+        assert_eq!(result.months[11].remaining, plain.months[11].remaining - dec!(10000));
+        assert_eq!(result.overall.grant, dec!(10000));
+        assert!(result.payoff_month.unwrap() < plain.payoff_month.unwrap());
+        assert!(result.cost() < plain.cost() - dec!(10000));
+        assert_rows_sum_to_totals(&result);
+    }
+
+    #[test]
+    fn repayment_grant_during_construction_after_it() {
+        let params = LoanParams {
+            disbursements: vec![
+                Disbursement { month: 1, amount: dec!(50000) },
+                Disbursement { month: 6, amount: dec!(50000) },
+            ],
+            repayment_grant: Some(RepaymentGrant {
+                month: 3,
+                amount: SpecialAmount::Fixed(dec!(5000)),
+            }),
+            ..annuity(dec!(100000), dec!(3), 20)
+        };
+        let result = calc(&params).unwrap();
+        assert_eq!(result.months[5].grant, dec!(0));
+        assert_eq!(result.months[6].grant, dec!(5000));
+        assert_eq!(result.overall.grant, dec!(5000));
+        assert_eq!(result.overall.remaining, dec!(0));
+        assert_rows_sum_to_totals(&result);
+    }
+
+    #[test]
+    fn linear_clears_equal_parts() {
+        let params = LoanParams {
+            loan_type: LoanType::Linear,
+            ..annuity(dec!(120000), dec!(3), 10)
+        };
+        let result = calc(&params).unwrap();
+        assert_eq!(result.months[0].payment, dec!(1300));
+        assert_eq!(result.months[1].payment, dec!(1297.50));
+        assert!(result.months.iter().all(|m| m.cleared_amount == dec!(1000)));
+        assert_eq!(result.overall.paid_interest, dec!(18150));
+        assert_eq!(result.payoff_month, Some(120));
+        assert_rows_sum_to_totals(&result);
+
+        let result = calc(&LoanParams {
+            repayment_free_months: 12,
+            ..params
+        }).unwrap();
+        assert_eq!(result.repayment_free_monthly_rate, Some(dec!(300)));
+        assert_eq!(result.months[11].payment, dec!(300));
+        assert_eq!(result.months[11].cleared_amount, dec!(0));
+        assert_eq!(result.months[12].cleared_amount, dec!(1111.11));
+        assert_eq!(result.overall.remaining, dec!(0));
+        assert_eq!(result.payoff_month, Some(120));
+        assert_rows_sum_to_totals(&result);
+    }
+
+    #[test]
+    fn building_savings_allotment() {
+        let params = LoanParams {
+            loan_type: LoanType::BuildingSavings,
+            annuity_mode: AnnuityMode::ClearanceRate,
+            clearance_rate: Percent(dec!(6)),
+            building_savings: BuildingSavingsParams {
+                monthly_deposit: dec!(500),
+                deposit_rate: dec!(0),
+                contract_fee: dec!(0),
+                minimum_balance: dec!(40),
+                minimum_rating: None,
+            },
+            ..annuity(dec!(50000), dec!(2), 30)
+        };
+        let result = calc(&params).unwrap();
+        let allotment = result.building_savings.as_ref().unwrap();
+        assert_eq!(allotment.allotment_month, 40);
+        assert_eq!(allotment.balance, dec!(20000));
+        assert_eq!(allotment.loan_amount, dec!(30000));
+        assert_eq!(allotment.loan_monthly_rate, dec!(200));
+        assert_eq!(result.months[38].savings_balance, dec!(19500));
+        assert_eq!(result.months[40].payment, dec!(200));
+        assert_eq!(result.months[40].paid_interest, dec!(50));
+        assert_eq!(result.overall.remaining, dec!(0));
+        assert!(result.payoff_month.is_some());
+
+        // The contract fee is taken from the first deposit and delays the allotment.
+        let result = calc(&LoanParams {
+            building_savings: BuildingSavingsParams {
+                contract_fee: dec!(1),
+                ..params.building_savings.clone()
+            },
+            ..params.clone()
+        }).unwrap();
+        assert_eq!(result.months[0].fees, dec!(500));
+        assert_eq!(result.building_savings.as_ref().unwrap().allotment_month, 41);
+
+        let result = calc(&LoanParams {
+            building_savings: BuildingSavingsParams {
+                minimum_rating: Some(dec!(100)),
+                ..params.building_savings.clone()
+            },
+            ..params
+        }).unwrap();
+        assert!(result.building_savings.unwrap().allotment_month > 40);
+    }
+
+    #[test]
+    fn rate_change_keeps_or_recomputes_payment() {
+        let params = LoanParams {
+            rate_changes: vec![RateChange { month: 61, interest_rate: dec!(5) }],
+            ..annuity(dec!(100000), dec!(3), 10)
+        };
+        let result = calc(&params).unwrap();
+        let before = result.months[59].remaining;
+        assert_eq!(result.months[60].payment, result.monthly_rate);
+        assert_eq!(result.months[60].paid_interest, (before * dec!(0.05) / dec!(12)).round_dp(2));
+        assert!(result.overall.remaining > dec!(0));
+        assert_eq!(result.payoff_month, None);
+
+        let result = calc(&LoanParams {
+            rate_change_mode: RateChangeMode::RecomputePayment,
+            ..params.clone()
+        }).unwrap();
+        assert!(result.months[60].payment > result.monthly_rate);
+        assert_eq!(result.months[61].payment, result.months[60].payment);
+        assert_eq!(result.overall.remaining, dec!(0));
+        assert_eq!(result.payoff_month, Some(120));
+        assert_rows_sum_to_totals(&result);
+
+        let result = calc(&LoanParams {
+            rate_change_mode: RateChangeMode::RecomputePayment,
+            annuity_mode: AnnuityMode::ClearanceRate,
+            clearance_rate: Percent(dec!(2)),
+            ..params
+        }).unwrap();
+        let before = result.months[59].remaining;
+        assert_eq!(result.monthly_rate, dec!(416.67));
+        assert_eq!(result.months[60].payment, (before * dec!(0.07) / dec!(12)).round_dp(2));
+    }
+
+    #[test]
+    fn implied_interest_rate_of_payment() {
+        let params = annuity(dec!(100000), dec!(0), 10);
+        let rate = implied_interest_rate(&params, dec!(965.61)).unwrap();
+        assert!((rate.nominal - dec!(3)).abs() < dec!(0.001), "{}", rate.nominal);
+
+        // Monthly fees are part of the payment and raise the effective rate.
+        let rate = implied_interest_rate(&LoanParams {
+            fees: LoanFees {
+                account_fee: dec!(5),
+                ..LoanFees::default()
+            },
+            ..params.clone()
+        }, dec!(970.61)).unwrap();
+        assert!((rate.nominal - dec!(3)).abs() < dec!(0.001), "{}", rate.nominal);
+        assert!(rate.effective.unwrap() > dec!(3.1));
+
+        match implied_interest_rate(&params, dec!(800)) {
+            Err(CalcErr::GoalNotReachable) => (),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+}
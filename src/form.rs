@@ -1,5 +1,6 @@
-use iced::{Element, text_input, text_input::TextInput, Column};
+use iced::{Element, text_input, text_input::TextInput, Column, Text};
 use crate::custom_text_input::CustomTextInput;
+use crate::style::{Colors, ErrorTextInputStyle};
 
 #[derive(Debug, Clone)]
 pub enum FormMessage<I: 'static+ Clone + Copy> {
@@ -18,6 +19,8 @@ pub struct FormTextInput<I: 'static+ Clone+ Copy> {
     id: I,
     value: String,
     placeholder: String,
+    visible: bool,
+    error: Option<String>,
 }
 
 impl<I: 'static+ Clone+ Copy> FormTextInput<I> {
@@ -26,7 +29,9 @@ impl<I: 'static+ Clone+ Copy> FormTextInput<I> {
             state: Default::default(),
             id,
             value,
-            placeholder: String::from(placeholder)
+            placeholder: String::from(placeholder),
+            visible: true,
+            error: None,
         }
     }
 
@@ -40,14 +45,22 @@ impl<I: 'static+ Clone+ Copy> FormTextInput<I> {
 
     pub fn view(&mut self) -> Element<FormTextInputMessage> {
         let has_focus = self.state.is_focused();
-        CustomTextInput::new(
-            TextInput::new(
-                &mut self.state,
-                &self.placeholder,
-                &self.value,
-                FormTextInputMessage::InputChanged)
-                .into(), has_focus, FormTextInputMessage::OnTab
-        ).into()
+        let mut input = TextInput::new(
+            &mut self.state,
+            &self.placeholder,
+            &self.value,
+            FormTextInputMessage::InputChanged);
+        if self.error.is_some() {
+            input = input.style(ErrorTextInputStyle {});
+        }
+        let input = CustomTextInput::new(input.into(), has_focus, FormTextInputMessage::OnTab);
+        match &self.error {
+            Some(error) => Column::new()
+                .push(input)
+                .push(Text::new(error).size(16).color(Colors::red()))
+                .into(),
+            None => input.into(),
+        }
     }
 
     pub fn update(&mut self, msg: FormTextInputMessage) {
@@ -76,21 +89,61 @@ impl<I: 'static+ Clone+ Copy> Form<I> {
         self
     }
 
+    pub fn value(&self, id: I) -> Option<&str> where I: PartialEq {
+        self.inputs.iter().find(|el| el.id == id).map(|el| el.value.as_str())
+    }
+
+    pub fn set_value(&mut self, id: I, value: String) where I: PartialEq {
+        for el in self.inputs.iter_mut().filter(|el| el.id == id) {
+            el.value = value.clone();
+        }
+    }
+
+    /// Replaces the values for which `value` returns one.
+    pub fn update_values(&mut self, mut value: impl FnMut(&I) -> Option<String>) {
+        for el in self.inputs.iter_mut() {
+            if let Some(value) = value(&el.id) {
+                el.value = value;
+            }
+        }
+    }
+
+    /// Shows the error `error` returns below each input, clearing the others.
+    pub fn update_errors(&mut self, error: impl Fn(&I) -> Option<String>) {
+        for el in self.inputs.iter_mut() {
+            el.error = error(&el.id);
+        }
+    }
+
+    pub fn update_visibility(&mut self, is_visible: impl Fn(&I) -> bool) {
+        for el in self.inputs.iter_mut() {
+            el.visible = is_visible(&el.id);
+            if !el.visible {
+                el.set_focus(false);
+            }
+        }
+    }
+
     pub fn select(&mut self, next: bool) {
-        let idx = self.inputs.iter()
-            .enumerate().find(|(_idx, e) |e.state.is_focused())
-            .map(|(idx, _e)| idx);
+        let visible = self.inputs.iter()
+            .enumerate().filter(|(_idx, e)| e.visible)
+            .map(|(idx, _e)| idx)
+            .collect::<Vec<usize>>();
+        let pos = visible.iter()
+            .position(|idx| self.inputs[*idx].state.is_focused());
 
-        if let Some(idx) = idx {
-            let next_idx = if next {
-              idx +1
+        if let Some(pos) = pos {
+            let next_pos = if next {
+              pos +1
             }else {
-                if idx == 0 { 0 } else { idx -1 }
+                if pos == 0 { 0 } else { pos -1 }
             };
-            self.set_focus(idx, false);
-            self.set_focus(next_idx, true);
-        }else {
-            self.set_focus(0, true);
+            self.set_focus(visible[pos], false);
+            if let Some(next_idx) = visible.get(next_pos) {
+                self.set_focus(*next_idx, true);
+            }
+        }else if let Some(first) = visible.first() {
+            self.set_focus(*first, true);
         }
     }
 
@@ -101,7 +154,7 @@ impl<I: 'static+ Clone+ Copy> Form<I> {
     }
 
     pub fn view(&mut self) -> Element<FormMessage<I>> {
-        self.inputs.iter_mut().enumerate().map(|(idx, el)| {
+        self.inputs.iter_mut().enumerate().filter(|(_idx, el)| el.visible).map(|(idx, el)| {
             let id = el.id.clone();
             el.view().map(move |m| FormMessage::TextInputMessage(id, idx, m))
         }).fold(Column::new().spacing(5), |acc, el| {
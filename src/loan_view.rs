@@ -1,8 +1,8 @@
-use crate::{LoanType};
-use rust_decimal_macros::*;
+use loan_calc::calc::{self, LoanType, AnnuityMode, LoanParams, LoanFees, BuildingSavingsParams, RepaymentVehicle, RepaymentVehicleKind, FixedPeriod, RateChange, RateChangeMode, PaymentFrequency, DayCount, InterestTiming, RoundingMode, SensitivityGrid, ImpliedRate, RateModel, SimulationParams, SimulationResult, CalcErr, Param, InvalidReason, MaxAmount, Money, Percent, Duration, Disbursement, EarlyRepayment, RepaymentGrant, SpecialRepayment, SpecialAmount, CalcResultOverview, CalcResult};
 use std::str::FromStr;
+use chrono::NaiveDate;
 
-use iced::{Button, button, Text, Element, Row, Column, Scrollable, Length};
+use iced::{Button, button, Text, Element, Row, Column, Scrollable, Length, Command};
 
 use serde::{Deserialize, Serialize};
 
@@ -13,27 +13,108 @@ use rust_decimal::Decimal;
 use crate::{
     style::ButtonStyle,
     form,
+    locale::NumberLocale,
 };
 use crate::form::{FormMessage, FormTextInputMessage};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct LoanViewData {
     pub name: String,
-    amount: String,
-    interest_rate: String,
-    clearance_rate: String,
-    runtime_years: String,
+    amount: Option<Money>,
+    interest_rate: Option<Percent>,
+    clearance_rate: Option<Percent>,
+    runtime: Option<Duration>,
     loan_type: LoanType,
+    #[serde(default)]
+    annuity_mode: AnnuityMode,
+    #[serde(default)]
+    repayment_free_months: String,
+    #[serde(default)]
+    fixed_period_years: String,
+    #[serde(default)]
+    follow_up_rate: String,
+    #[serde(default)]
+    follow_up_clearance_rate: String,
+    #[serde(default)]
+    rate_changes: String,
+    #[serde(default)]
+    rate_change_mode: RateChangeMode,
+    #[serde(default)]
+    special_repayments: String,
+    #[serde(default)]
+    special_repayment_cap: String,
+    #[serde(default)]
+    monthly_deposit: String,
+    #[serde(default)]
+    deposit_rate: String,
+    #[serde(default)]
+    contract_fee: String,
+    #[serde(default)]
+    minimum_balance: String,
+    #[serde(default)]
+    minimum_rating: String,
+    #[serde(default)]
+    repayment_vehicle: Option<RepaymentVehicleKind>,
+    #[serde(default)]
+    vehicle_contribution: String,
+    #[serde(default)]
+    vehicle_return_rate: String,
+    #[serde(default)]
+    vehicle_fee: String,
+    #[serde(default)]
+    processing_fee: String,
+    #[serde(default)]
+    disagio: String,
+    #[serde(default)]
+    account_fee: String,
+    #[serde(default)]
+    insurance: String,
+    #[serde(default)]
+    payment_frequency: PaymentFrequency,
+    #[serde(default)]
+    day_count: DayCount,
+    #[serde(default)]
+    interest_timing: InterestTiming,
+    #[serde(default)]
+    start_date: String,
+    #[serde(default)]
+    payment_day: String,
+    #[serde(default)]
+    rounding_mode: RoundingMode,
+    #[serde(default)]
+    disbursements: String,
+    #[serde(default)]
+    commitment_rate: String,
+    #[serde(default)]
+    commitment_free_months: String,
+    #[serde(default)]
+    repayment_grant: String,
+    #[serde(default)]
+    early_repayment: String,
+    #[serde(default)]
+    reinvestment_rate: String,
+    #[serde(default)]
+    budget: String,
+    #[serde(default)]
+    target_remaining: String,
+    #[serde(default)]
+    rate_model: RateModel,
+    #[serde(default)]
+    rate_volatility: String,
+    #[serde(default)]
+    simulation_runs: String,
 }
 
 #[derive(Default)]
 pub struct LoanView {
     state: LoanViewState,
     pub data: LoanViewData,
-    pub result: Option<CalcResultOverview>
+    pub result: Option<CalcResultOverview>,
+    sensitivity: Option<SensitivityGrid>,
+    locale: NumberLocale,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum LoanFormData {
     None,
     Name,
@@ -41,6 +122,99 @@ pub enum LoanFormData {
     InterestRate,
     ClearanceRate,
     RuntimeYears,
+    StartDate,
+    PaymentDay,
+    RepaymentFreeMonths,
+    FixedPeriodYears,
+    FollowUpRate,
+    FollowUpClearanceRate,
+    RateChanges,
+    SpecialRepayments,
+    SpecialRepaymentCap,
+    Disbursements,
+    CommitmentRate,
+    CommitmentFreeMonths,
+    RepaymentGrant,
+    EarlyRepayment,
+    ReinvestmentRate,
+    Budget,
+    TargetRemaining,
+    RateVolatility,
+    SimulationRuns,
+    MonthlyDeposit,
+    DepositRate,
+    ContractFee,
+    MinimumBalance,
+    MinimumRating,
+    VehicleContribution,
+    VehicleReturnRate,
+    VehicleFee,
+    ProcessingFee,
+    Disagio,
+    AccountFee,
+    Insurance,
+}
+
+impl LoanFormData {
+    /// Whether the input is a single number, entered in the number locale.
+    fn is_numeric(&self) -> bool {
+        !matches!(self,
+            LoanFormData::None |
+            LoanFormData::Name |
+            LoanFormData::StartDate |
+            LoanFormData::RateChanges |
+            LoanFormData::SpecialRepayments |
+            LoanFormData::Disbursements |
+            LoanFormData::RepaymentGrant |
+            LoanFormData::EarlyRepayment
+        )
+    }
+
+    /// Fields holding `key: value` entries with a number as value.
+    fn is_entry_list(&self) -> bool {
+        matches!(self,
+            LoanFormData::RateChanges |
+            LoanFormData::SpecialRepayments |
+            LoanFormData::Disbursements |
+            LoanFormData::RepaymentGrant
+        )
+    }
+
+    fn is_used_by(&self, data: &LoanViewData) -> bool {
+        match self {
+            LoanFormData::ClearanceRate => match data.loan_type {
+                LoanType::Annuity => matches!(data.annuity_mode, AnnuityMode::ClearanceRate),
+                LoanType::BuildingSavings => true,
+                LoanType::Linear | LoanType::Bullet => false,
+            },
+            LoanFormData::RepaymentFreeMonths |
+            LoanFormData::RepaymentGrant => matches!(data.loan_type, LoanType::Annuity | LoanType::Linear),
+            LoanFormData::RateChanges => !matches!(data.loan_type, LoanType::BuildingSavings),
+            LoanFormData::FixedPeriodYears |
+            LoanFormData::FollowUpRate |
+            LoanFormData::FollowUpClearanceRate |
+            LoanFormData::SpecialRepayments |
+            LoanFormData::SpecialRepaymentCap |
+            LoanFormData::Disbursements => matches!(data.loan_type, LoanType::Annuity),
+            LoanFormData::EarlyRepayment |
+            LoanFormData::Budget |
+            LoanFormData::TargetRemaining => matches!(data.loan_type, LoanType::Annuity),
+            LoanFormData::RateVolatility |
+            LoanFormData::SimulationRuns => matches!(data.loan_type, LoanType::Annuity) && !data.fixed_period_years.trim().is_empty(),
+            LoanFormData::ReinvestmentRate => matches!(data.loan_type, LoanType::Annuity) && !data.early_repayment.trim().is_empty(),
+            LoanFormData::CommitmentRate |
+            LoanFormData::CommitmentFreeMonths => matches!(data.loan_type, LoanType::Annuity) && !data.disbursements.trim().is_empty(),
+            LoanFormData::MonthlyDeposit |
+            LoanFormData::DepositRate |
+            LoanFormData::ContractFee |
+            LoanFormData::MinimumBalance |
+            LoanFormData::MinimumRating => matches!(data.loan_type, LoanType::BuildingSavings),
+            LoanFormData::VehicleContribution |
+            LoanFormData::VehicleReturnRate |
+            LoanFormData::VehicleFee => matches!(data.loan_type, LoanType::Bullet) && data.repayment_vehicle.is_some(),
+            _ => true,
+        }
+    }
 }
 
 impl Default for LoanFormData {
@@ -49,51 +223,632 @@ impl Default for LoanFormData {
     }
 }
 
+/// Outcome of a goal seek that can be written back into the form.
+enum GoalSeek {
+    Amount(MaxAmount),
+    ClearanceRate(Decimal),
+    InterestRate(ImpliedRate),
+    NotReachable,
+}
+
 #[derive(Default)]
 struct LoanViewState {
     form: form::Form<LoanFormData>,
     calc_button: button::State,
+    error: Option<String>,
+    /// The result does not match the inputs, which are invalid.
+    stale: bool,
+    /// Counts input changes, so a sensitivity grid computed for older inputs is dropped.
+    generation: u64,
+    /// Input generation the sensitivity grid was computed for.
+    sensitivity_generation: u64,
     result_scroller: iced::scrollable::State,
+    sensitivity_btn: button::State,
+    show_sensitivity: bool,
+    max_amount_btn: button::State,
+    clearance_rate_btn: button::State,
+    implied_rate_btn: button::State,
+    apply_goal_seek_btn: button::State,
+    goal_seek: Option<GoalSeek>,
+    normal_model_btn: button::State,
+    random_walk_btn: button::State,
+    simulate_btn: button::State,
+    simulating: bool,
+    simulation: Option<Result<SimulationResult, CalcErr>>,
     annuity_btn: button::State,
     building_savings_btn: button::State,
+    linear_btn: button::State,
+    bullet_btn: button::State,
+    no_vehicle_btn: button::State,
+    savings_plan_btn: button::State,
+    building_savings_vehicle_btn: button::State,
+    clearance_rate_mode_btn: button::State,
+    runtime_mode_btn: button::State,
+    keep_payment_btn: button::State,
+    recompute_payment_btn: button::State,
+    monthly_btn: button::State,
+    quarterly_btn: button::State,
+    semi_annual_btn: button::State,
+    annual_btn: button::State,
+    thirty_360_btn: button::State,
+    act_360_btn: button::State,
+    act_365_btn: button::State,
+    in_arrears_btn: button::State,
+    in_advance_btn: button::State,
+    half_up_btn: button::State,
+    half_even_btn: button::State,
+    round_down_btn: button::State,
+    round_up_btn: button::State,
 }
 
 #[derive(Debug, Clone)]
 pub enum LoanViewMessage {
     ChangeTypeToAnnuity,
     ChangeTypeToBuildingSavings,
+    ChangeTypeToLinear,
+    ChangeTypeToBullet,
+    ChangeRepaymentVehicle(Option<RepaymentVehicleKind>),
+    ChangeAnnuityModeToClearanceRate,
+    ChangeAnnuityModeToRuntime,
+    ChangeRateChangeModeToKeepPayment,
+    ChangeRateChangeModeToRecomputePayment,
+    ChangePaymentFrequency(PaymentFrequency),
+    ChangeDayCount(DayCount),
+    ChangeInterestTiming(InterestTiming),
+    ChangeRoundingMode(RoundingMode),
+    ToggleSensitivity,
+    SeekMaxAmount,
+    SeekClearanceRate,
+    SeekInterestRate,
+    ApplyGoalSeek,
+    /// Typing paused after the input change with the given generation.
+    InputPaused(u64),
+    /// Sensitivity grid computed for the inputs of the given generation.
+    SensitivityDone(u64, Option<SensitivityGrid>),
+    ChangeRateModel(RateModel),
+    RunSimulation,
+    SimulationDone(Result<SimulationResult, CalcErr>),
     Calc,
     LoanForm(FormMessage<LoanFormData>),
 }
 
+/// Title and amount of a schedule column.
+type ScheduleColumn = (&'static str, fn(&CalcResult) -> Decimal);
+
+/// Schedule with a column per amount of a month. Fees, earned interest and the balance of a
+/// repayment vehicle or savings contract are only shown if the schedule has them.
+fn schedule_view<'a>(months: &[Box<CalcResult>]) -> Column<'a, LoanViewMessage> {
+    let has = |f: fn(&CalcResult) -> Decimal| months.iter().any(|m| f(m) != Decimal::new(0, 0));
+    let mut columns: Vec<ScheduleColumn> = vec![
+        ("Payment", |m| m.payment),
+        ("Remaining", |m| m.remaining),
+        ("Cleared", |m| m.cleared_amount),
+        ("Interest", |m| m.paid_interest),
+    ];
+    let optional: [ScheduleColumn; 3] = [
+        ("Fees", |m| m.fees),
+        ("Earned interest", |m| m.earned_interest),
+        ("Balance", |m| m.savings_balance),
+    ];
+    columns.extend(optional.iter().filter(|(_, f)| has(*f)).cloned());
+
+    let header = columns.iter().fold(Row::new().push(Text::new("Month").width(Length::Units(160))), |acc, (title, _)| {
+        acc.push(Text::new(*title).width(Length::Units(120)))
+    });
+    months.iter().fold(Column::new().push(header), |acc, month| {
+        acc.push(month_view(month, &columns))
+    })
+}
+
+fn month_view<'a>(month: &CalcResult, columns: &[ScheduleColumn]) -> Row<'a, LoanViewMessage> {
+    let label = match month.date {
+        Some(date) => format!("{} ({})", month.month, date),
+        None => month.month.to_string(),
+    };
+    columns.iter().fold(Row::new().push(Text::new(label).width(Length::Units(160))), |acc, (_, f)| {
+        acc.push(Text::new(f(month).to_string()).width(Length::Units(120)))
+    })
+}
+
+/// Offsets from `-spread` to `spread` in steps of `step`.
+fn offsets(spread: Decimal, step: Decimal) -> Vec<Decimal> {
+    let mut offsets = vec![];
+    let mut offset = -spread;
+    while offset <= spread {
+        offsets.push(offset);
+        offset += step;
+    }
+    offsets
+}
+
+fn sensitivity_grid(params: &LoanParams) -> Option<SensitivityGrid> {
+    match params.loan_type {
+        LoanType::Annuity => calc::sensitivity_grid(
+            params,
+            &offsets(Decimal::new(2, 0), Decimal::new(25, 2)),
+            &offsets(Decimal::new(1, 0), Decimal::new(5, 1)),
+        ).ok(),
+        _ => None,
+    }
+}
+
+/// Grid of payment, paid interest and remaining debt per interest rate (rows) and clearance rate (columns).
+fn sensitivity_view<'a>(grid: &SensitivityGrid) -> Column<'a, LoanViewMessage> {
+    let cell_width = Length::Units(160);
+    let header = if grid.clearance_rates.is_empty() {
+        Row::new().push(Text::new("By runtime").width(cell_width))
+    }else {
+        grid.clearance_rates.iter().fold(Row::new(), |acc, c| {
+            acc.push(Text::new(format!("Clearance {} %", c)).width(cell_width))
+        })
+    };
+    let header = Row::new()
+        .push(Text::new("Interest").width(Length::Units(100)))
+        .push(header);
+    grid.interest_rates.iter().zip(grid.cells.iter()).fold(Column::new().spacing(8).push(Text::new("Rate, paid interest and remaining debt per cell")).push(header), |acc, (rate, cells)| {
+        let row = cells.iter().fold(Row::new().push(Text::new(format!("{} %", rate)).width(Length::Units(100))), |acc, cell| {
+            let text = match cell {
+                Some(cell) => format!("{}\n{}\n{}", cell.monthly_rate.round_dp(2), cell.paid_interest.round_dp(2), cell.remaining.round_dp(2)),
+                None => String::from("-"),
+            };
+            acc.push(Text::new(text).width(cell_width))
+        });
+        acc.push(row)
+    })
+}
+
+/// Percentiles of a simulation of follow-up rates.
+fn simulation_summary(simulation: &SimulationResult) -> String {
+    let payoff = |month: Option<i32>, date: Option<NaiveDate>| match (month, date) {
+        (Some(month), Some(date)) => format!("month {} ({})", month, date),
+        (Some(month), None) => format!("month {}", month),
+        (None, _) => String::from("not paid off"),
+    };
+    format!("{} runs, 10th / 50th / 90th percentile\nFollow-up rate: {} % / {} % / {} %\nPaid interest: {} / {} / {}\nFollow-up payment: {} / {} / {}\nPaid off: {} / {} / {}",
+            simulation.runs,
+            simulation.follow_up_rate.p10, simulation.follow_up_rate.p50, simulation.follow_up_rate.p90,
+            simulation.paid_interest.p10.round_dp(2), simulation.paid_interest.p50.round_dp(2), simulation.paid_interest.p90.round_dp(2),
+            simulation.follow_up_monthly_rate.p10.round_dp(2), simulation.follow_up_monthly_rate.p50.round_dp(2), simulation.follow_up_monthly_rate.p90.round_dp(2),
+            payoff(simulation.payoff_month.p10, simulation.payoff_date.p10),
+            payoff(simulation.payoff_month.p50, simulation.payoff_date.p50),
+            payoff(simulation.payoff_month.p90, simulation.payoff_date.p90),
+    )
+}
+
+/// Invalid input shown below its form field.
+#[derive(Debug, Clone)]
+pub struct FieldErr {
+    pub field: LoanFormData,
+    pub message: String,
+    /// A required input is empty.
+    pub missing: bool,
+}
+
+/// Collects the errors of all inputs instead of stopping at the first one. Invalid inputs
+/// are replaced by their default.
+#[derive(Default)]
+struct FieldErrors(Vec<FieldErr>);
+
+impl FieldErrors {
+    fn check<T: Default>(&mut self, field: LoanFormData, result: Result<T, Box<dyn Error>>) -> T {
+        result.unwrap_or_else(|e| {
+            self.0.push(FieldErr { field, message: e.to_string(), missing: false });
+            T::default()
+        })
+    }
+
+    fn required<T: FromStr + Default>(&mut self, field: LoanFormData, value: &str) -> T {
+        if value.trim().is_empty() {
+            self.0.push(FieldErr { field, message: String::from("Required"), missing: true });
+            return T::default();
+        }
+        let result = value.trim().parse::<T>().map_err(|_| format!("Invalid value '{}'", value.trim()).into());
+        self.check(field, result)
+    }
+
+    fn typed<T: Default>(&mut self, field: LoanFormData, value: Option<T>) -> T {
+        value.unwrap_or_else(|| {
+            self.0.push(FieldErr { field, message: String::from("Required"), missing: true });
+            T::default()
+        })
+    }
+
+    fn optional<T: FromStr>(&mut self, field: LoanFormData, value: &str) -> Option<T> {
+        let result = parse_optional::<T>(value).map_err(|_| format!("Invalid value '{}'", value.trim()).into());
+        self.check(field, result)
+    }
+}
+
+/// Input field of an engine parameter.
+fn param_field(param: Param) -> LoanFormData {
+    match param {
+        Param::Amount => LoanFormData::Amount,
+        Param::InterestRate => LoanFormData::InterestRate,
+        Param::ClearanceRate => LoanFormData::ClearanceRate,
+        Param::Runtime => LoanFormData::RuntimeYears,
+        Param::RepaymentFreeMonths => LoanFormData::RepaymentFreeMonths,
+        Param::FixedPeriod => LoanFormData::FixedPeriodYears,
+        Param::FollowUpRate => LoanFormData::FollowUpRate,
+        Param::FollowUpClearanceRate => LoanFormData::FollowUpClearanceRate,
+        Param::RateChanges => LoanFormData::RateChanges,
+        Param::SpecialRepayments => LoanFormData::SpecialRepayments,
+        Param::CommitmentRate => LoanFormData::CommitmentRate,
+        Param::ProcessingFee => LoanFormData::ProcessingFee,
+        Param::Disagio => LoanFormData::Disagio,
+        Param::AccountFee => LoanFormData::AccountFee,
+        Param::Insurance => LoanFormData::Insurance,
+        Param::EarlyRepayment => LoanFormData::EarlyRepayment,
+    }
+}
+
+/// Field errors of a failed calculation, `None` if the error is not caused by a single input.
+fn calc_field_errors(err: &CalcErr) -> Option<Vec<FieldErr>> {
+    let error = |field: LoanFormData, message: &str| FieldErr { field, message: String::from(message), missing: false };
+    match err {
+        CalcErr::InvalidParams(invalid) => Some(invalid.iter().map(|i| error(param_field(i.param), match i.reason {
+            InvalidReason::Negative => "Must not be negative",
+            InvalidReason::RateTooHigh => "Rate too high",
+            InvalidReason::Zero => "Must be greater than zero",
+            InvalidReason::TooLong if matches!(i.param, Param::Runtime) => "Runtime too long",
+            InvalidReason::TooLong => "Longer than the runtime",
+            InvalidReason::PaymentBelowInterest => "Payment does not cover the interest",
+            InvalidReason::BeforeStart => "Must not be before the start",
+        })).collect()),
+        CalcErr::RuntimeZero => Some(vec![error(LoanFormData::RuntimeYears, "No repayment period left")]),
+        CalcErr::DisbursementMismatch => Some(vec![error(LoanFormData::Disbursements, "Disbursements must add up to the amount")]),
+        CalcErr::SpecialRepaymentCapExceeded(year) => Some(vec![FieldErr {
+            field: LoanFormData::SpecialRepayments,
+            message: format!("Cap exceeded in year {}", year),
+            missing: false,
+        }]),
+        CalcErr::GoalNotReachable | CalcErr::FixedPeriodMissing | CalcErr::Overflow => None,
+    }
+}
+
+/// Goal seek result, not reachable if the calculation fails for other reasons than the inputs.
+fn goal_seek_result(result: Result<GoalSeek, CalcErr>) -> Result<GoalSeek, Vec<FieldErr>> {
+    match result {
+        Ok(goal_seek) => Ok(goal_seek),
+        Err(e) => match calc_field_errors(&e) {
+            Some(errors) => Err(errors),
+            None => Ok(GoalSeek::NotReachable),
+        },
+    }
+}
+
+fn parse_optional<T: FromStr>(value: &str) -> Result<Option<T>, T::Err> {
+    if value.trim().is_empty() {
+        Ok(None)
+    }else {
+        value.trim().parse::<T>().map(Some)
+    }
+}
+
+/// Splits a list of `month: value` entries separated by `;`.
+fn parse_month_entries(value: &str) -> Result<Vec<(i32, &str)>, Box<dyn Error>> {
+    let mut entries = vec![];
+    for entry in value.split(';').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        let mut parts = entry.splitn(2, ':');
+        let month = parts.next().unwrap_or("").trim().parse::<i32>()?;
+        let value = parts.next().ok_or(format!("Missing value in entry '{}'", entry))?.trim();
+        entries.push((month, value));
+    }
+    Ok(entries)
+}
+
+/// Applies `f` to the number of every `key: value` entry, keeping a `%` or `/y` after it.
+fn map_entry_numbers(value: &str, f: impl Fn(&str) -> String) -> String {
+    value.split(';')
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let mut parts = entry.splitn(2, ':');
+            let key = parts.next().unwrap_or("").trim();
+            match parts.next() {
+                Some(number) => {
+                    let number = number.trim();
+                    let (number, yearly) = match number.strip_suffix("/y") {
+                        Some(number) => (number.trim(), "/y"),
+                        None => (number, ""),
+                    };
+                    let (number, percent) = match number.strip_suffix('%') {
+                        Some(number) => (number.trim(), "%"),
+                        None => (number, ""),
+                    };
+                    format!("{}: {}{}{}", key, f(number), percent, yearly)
+                }
+                None => entry.to_owned(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+/// Parses an amount, a `%` after it refers to the loan amount.
+fn parse_special_amount(amount: &str) -> Result<SpecialAmount, Box<dyn Error>> {
+    Ok(if amount.ends_with('%') {
+        SpecialAmount::PercentOfPrincipal(Decimal::from_str(amount.trim_end_matches('%').trim())?)
+    }else {
+        SpecialAmount::Fixed(Decimal::from_str(amount)?)
+    })
+}
+
+/// Parses a repayment grant given as a single `month: amount` entry.
+fn parse_repayment_grant(value: &str) -> Result<Option<RepaymentGrant>, Box<dyn Error>> {
+    let entries = parse_month_entries(value)?;
+    match entries.as_slice() {
+        [] => Ok(None),
+        [(month, amount)] => Ok(Some(RepaymentGrant {
+            month: *month,
+            amount: parse_special_amount(amount)?,
+        })),
+        _ => Err("Only one repayment grant is supported".into()),
+    }
+}
+
+/// Parses special repayments given as `month: amount` entries.
+/// A `%` after the amount refers to the loan amount, a trailing `/y` repeats the entry yearly.
+fn parse_special_repayments(value: &str) -> Result<Vec<SpecialRepayment>, Box<dyn Error>> {
+    let mut special_repayments = vec![];
+    for (month, mut amount) in parse_month_entries(value)? {
+        let yearly = amount.ends_with("/y");
+        if yearly {
+            amount = amount.trim_end_matches("/y").trim();
+        }
+        special_repayments.push(SpecialRepayment {
+            month,
+            amount: parse_special_amount(amount)?,
+            yearly
+        });
+    }
+    Ok(special_repayments)
+}
+
+/// Parses interest rate changes given as `month: rate` entries.
+fn parse_rate_changes(value: &str) -> Result<Vec<RateChange>, Box<dyn Error>> {
+    let mut rate_changes = vec![];
+    for (month, rate) in parse_month_entries(value)? {
+        rate_changes.push(RateChange {
+            month,
+            interest_rate: Decimal::from_str(rate)?,
+        });
+    }
+    Ok(rate_changes)
+}
 
-#[derive(Default, Debug)]
-pub struct CalcResultOverview {
-    pub overall: CalcResult,
-    pub monthly_rate: Decimal,
-    months: Vec<Box<CalcResult>>
+/// Parses a month or a date, which is converted to the month it falls in counted from the start date.
+fn parse_month_or_date(value: &str, dates: &LoanParams) -> Result<i32, Box<dyn Error>> {
+    match value.parse::<i32>() {
+        Ok(month) => Ok(month),
+        Err(_) => {
+            let date = value.parse::<NaiveDate>()?;
+            Ok(dates.month_at(date).ok_or("Dates require a start date")?)
+        }
+    }
 }
 
-#[derive(Default, Debug)]
-pub struct CalcResult {
-    month: i32,
-    pub remaining: Decimal,
-    pub paid_interest: Decimal,
-    pub cleared_amount: Decimal,
+/// Parses the disbursement plan given as `month: amount` entries. Instead of the month a
+/// payout date can be given, which requires the loan's start date.
+fn parse_disbursements(value: &str, dates: &LoanParams) -> Result<Vec<Disbursement>, Box<dyn Error>> {
+    let mut disbursements = vec![];
+    for entry in value.split(';').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        let mut parts = entry.splitn(2, ':');
+        let key = parts.next().unwrap_or("").trim();
+        let amount = parts.next().ok_or(format!("Missing value in entry '{}'", entry))?.trim();
+        disbursements.push(Disbursement {
+            month: parse_month_or_date(key, dates)?,
+            amount: Decimal::from_str(amount)?,
+        });
+    }
+    Ok(disbursements)
 }
 
-impl CalcResult {
-    fn view(&mut self) -> Element<LoanViewMessage> {
-        Text::new(format!(
-            "{} - {} - {} - {}", self.month, self.remaining, self.cleared_amount, self.paid_interest
-        )).into()
+impl LoanViewData {
+    /// Parses the inputs, collecting the errors of all invalid inputs.
+    pub fn params(&self) -> Result<LoanParams, Vec<FieldErr>> {
+        let mut errors = FieldErrors::default();
+        // Start date and payment day to map dates of entries to months.
+        let dates = LoanParams {
+            start_date: errors.optional(LoanFormData::StartDate, &self.start_date),
+            payment_day: match errors.optional::<u32>(LoanFormData::PaymentDay, &self.payment_day) {
+                Some(day) if !(1..=31).contains(&day) => errors.check(LoanFormData::PaymentDay, Err("Must be between 1 and 31".into())),
+                day => day,
+            },
+            ..LoanParams::default()
+        };
+        let params = LoanParams {
+            loan_type: self.loan_type.clone(),
+            annuity_mode: self.annuity_mode.clone(),
+            amount: errors.typed(LoanFormData::Amount, self.amount),
+            interest_rate: errors.typed(LoanFormData::InterestRate, self.interest_rate),
+            clearance_rate: match (&self.loan_type, &self.annuity_mode) {
+                (LoanType::Annuity, AnnuityMode::Runtime) | (LoanType::Linear, _) | (LoanType::Bullet, _) => Percent::default(),
+                _ => errors.typed(LoanFormData::ClearanceRate, self.clearance_rate),
+            },
+            runtime: errors.typed(LoanFormData::RuntimeYears, self.runtime),
+            repayment_free_months: errors.optional(LoanFormData::RepaymentFreeMonths, &self.repayment_free_months).unwrap_or(0),
+            fixed_period: errors.optional::<i32>(LoanFormData::FixedPeriodYears, &self.fixed_period_years).map(|years| FixedPeriod {
+                years,
+                follow_up_rate: errors.optional(LoanFormData::FollowUpRate, &self.follow_up_rate),
+                follow_up_clearance_rate: errors.optional(LoanFormData::FollowUpClearanceRate, &self.follow_up_clearance_rate),
+            }),
+            rate_changes: errors.check(LoanFormData::RateChanges, parse_rate_changes(&self.rate_changes)),
+            rate_change_mode: self.rate_change_mode.clone(),
+            special_repayments: errors.check(LoanFormData::SpecialRepayments, parse_special_repayments(&self.special_repayments)),
+            special_repayment_cap: errors.optional(LoanFormData::SpecialRepaymentCap, &self.special_repayment_cap),
+            building_savings: BuildingSavingsParams {
+                monthly_deposit: errors.optional(LoanFormData::MonthlyDeposit, &self.monthly_deposit).unwrap_or_default(),
+                deposit_rate: errors.optional(LoanFormData::DepositRate, &self.deposit_rate).unwrap_or_default(),
+                contract_fee: errors.optional(LoanFormData::ContractFee, &self.contract_fee).unwrap_or_default(),
+                minimum_balance: errors.optional(LoanFormData::MinimumBalance, &self.minimum_balance).unwrap_or_default(),
+                minimum_rating: errors.optional(LoanFormData::MinimumRating, &self.minimum_rating),
+            },
+            repayment_vehicle: self.repayment_vehicle.as_ref().map(|kind| RepaymentVehicle {
+                kind: kind.clone(),
+                monthly_contribution: errors.required(LoanFormData::VehicleContribution, &self.vehicle_contribution),
+                return_rate: errors.optional(LoanFormData::VehicleReturnRate, &self.vehicle_return_rate).unwrap_or_default(),
+                fee: errors.optional(LoanFormData::VehicleFee, &self.vehicle_fee).unwrap_or_default(),
+            }),
+            fees: LoanFees {
+                processing_fee: errors.optional(LoanFormData::ProcessingFee, &self.processing_fee).unwrap_or_default(),
+                disagio: errors.optional(LoanFormData::Disagio, &self.disagio).unwrap_or_default(),
+                account_fee: errors.optional(LoanFormData::AccountFee, &self.account_fee).unwrap_or_default(),
+                insurance: errors.optional(LoanFormData::Insurance, &self.insurance).unwrap_or_default(),
+            },
+            payment_frequency: self.payment_frequency.clone(),
+            day_count: self.day_count.clone(),
+            interest_timing: self.interest_timing.clone(),
+            start_date: dates.start_date,
+            payment_day: dates.payment_day,
+            rounding_mode: self.rounding_mode.clone(),
+            disbursements: match &self.loan_type {
+                LoanType::Annuity => errors.check(LoanFormData::Disbursements, parse_disbursements(&self.disbursements, &dates)),
+                _ => vec![],
+            },
+            commitment_rate: errors.optional(LoanFormData::CommitmentRate, &self.commitment_rate).unwrap_or_default(),
+            commitment_free_months: errors.optional(LoanFormData::CommitmentFreeMonths, &self.commitment_free_months).unwrap_or(0),
+            early_repayment: match (&self.loan_type, self.early_repayment.trim()) {
+                (LoanType::Annuity, early_repayment) if !early_repayment.is_empty() => Some(EarlyRepayment {
+                    month: errors.check(LoanFormData::EarlyRepayment, parse_month_or_date(early_repayment, &dates)),
+                    reinvestment_rate: errors.required(LoanFormData::ReinvestmentRate, &self.reinvestment_rate),
+                }),
+                _ => None,
+            },
+            repayment_grant: match &self.loan_type {
+                LoanType::Annuity | LoanType::Linear => errors.check(LoanFormData::RepaymentGrant, parse_repayment_grant(&self.repayment_grant)),
+                _ => None,
+            },
+        };
+        if errors.0.is_empty() {
+            Ok(params)
+        }else {
+            Err(errors.0)
+        }
+    }
+
+    /// Text input of the fields without a typed value.
+    fn field_mut(&mut self, id: LoanFormData) -> Option<&mut String> {
+        Some(match id {
+            LoanFormData::None |
+            LoanFormData::Amount |
+            LoanFormData::InterestRate |
+            LoanFormData::ClearanceRate |
+            LoanFormData::RuntimeYears => return None,
+            LoanFormData::Name => &mut self.name,
+            LoanFormData::StartDate => &mut self.start_date,
+            LoanFormData::PaymentDay => &mut self.payment_day,
+            LoanFormData::RepaymentFreeMonths => &mut self.repayment_free_months,
+            LoanFormData::FixedPeriodYears => &mut self.fixed_period_years,
+            LoanFormData::FollowUpRate => &mut self.follow_up_rate,
+            LoanFormData::FollowUpClearanceRate => &mut self.follow_up_clearance_rate,
+            LoanFormData::RateChanges => &mut self.rate_changes,
+            LoanFormData::SpecialRepayments => &mut self.special_repayments,
+            LoanFormData::SpecialRepaymentCap => &mut self.special_repayment_cap,
+            LoanFormData::Disbursements => &mut self.disbursements,
+            LoanFormData::CommitmentRate => &mut self.commitment_rate,
+            LoanFormData::CommitmentFreeMonths => &mut self.commitment_free_months,
+            LoanFormData::RepaymentGrant => &mut self.repayment_grant,
+            LoanFormData::EarlyRepayment => &mut self.early_repayment,
+            LoanFormData::ReinvestmentRate => &mut self.reinvestment_rate,
+            LoanFormData::Budget => &mut self.budget,
+            LoanFormData::TargetRemaining => &mut self.target_remaining,
+            LoanFormData::RateVolatility => &mut self.rate_volatility,
+            LoanFormData::SimulationRuns => &mut self.simulation_runs,
+            LoanFormData::MonthlyDeposit => &mut self.monthly_deposit,
+            LoanFormData::DepositRate => &mut self.deposit_rate,
+            LoanFormData::ContractFee => &mut self.contract_fee,
+            LoanFormData::MinimumBalance => &mut self.minimum_balance,
+            LoanFormData::MinimumRating => &mut self.minimum_rating,
+            LoanFormData::VehicleContribution => &mut self.vehicle_contribution,
+            LoanFormData::VehicleReturnRate => &mut self.vehicle_return_rate,
+            LoanFormData::VehicleFee => &mut self.vehicle_fee,
+            LoanFormData::ProcessingFee => &mut self.processing_fee,
+            LoanFormData::Disagio => &mut self.disagio,
+            LoanFormData::AccountFee => &mut self.account_fee,
+            LoanFormData::Insurance => &mut self.insurance,
+        })
+    }
+
+    /// Input text of the typed fields, `None` for the other fields.
+    fn typed_input(&self, id: LoanFormData, locale: NumberLocale) -> Option<String> {
+        let text = match id {
+            LoanFormData::Amount => self.amount.map(|a| a.0.to_string()),
+            LoanFormData::InterestRate => self.interest_rate.map(|r| r.0.to_string()),
+            LoanFormData::ClearanceRate => self.clearance_rate.map(|r| r.0.to_string()),
+            LoanFormData::RuntimeYears => self.runtime.map(|r| r.years().to_string()),
+            _ => return None,
+        };
+        Some(text.map(|t| locale.format(&t)).unwrap_or_default())
+    }
+
+    /// Takes over the input of field `id`, parsing numbers and the numbers of entries in `locale`.
+    /// Typed fields are cleared if the input does not parse.
+    fn set_input(&mut self, id: LoanFormData, value: &str, locale: NumberLocale) {
+        match id {
+            LoanFormData::Amount => self.amount = locale.parse(value).ok().map(Money),
+            LoanFormData::InterestRate => self.interest_rate = locale.parse(value).ok().map(Percent),
+            LoanFormData::ClearanceRate => self.clearance_rate = locale.parse(value).ok().map(Percent),
+            LoanFormData::RuntimeYears => self.runtime = locale.parse(value).ok().map(Duration::from_years),
+            _ => {
+                let value = if id.is_numeric() {
+                    locale.normalize(value)
+                }else if id.is_entry_list() {
+                    map_entry_numbers(value, |n| locale.normalize(n))
+                }else {
+                    value.to_owned()
+                };
+                if let Some(field) = self.field_mut(id) {
+                    *field = value;
+                }
+            }
+        }
+    }
+
+    /// Reads loans from a `.lc` file. Files written before the amount, rates and runtime
+    /// were typed stored them as text and the runtime in years.
+    pub fn from_file(content: &[u8]) -> Result<Vec<LoanViewData>, serde_json::Error> {
+        let mut loans = serde_json::from_slice::<Vec<serde_json::Value>>(content)?;
+        for loan in loans.iter_mut().filter_map(|l| l.as_object_mut()) {
+            for key in &["amount", "interest_rate", "clearance_rate"] {
+                if let Some(serde_json::Value::String(text)) = loan.get(*key) {
+                    let value = match Decimal::from_str(text.trim()) {
+                        Ok(value) => serde_json::Value::String(value.to_string()),
+                        Err(_) => serde_json::Value::Null,
+                    };
+                    loan.insert(key.to_string(), value);
+                }
+            }
+            if let Some(runtime_years) = loan.remove("runtime_years") {
+                let runtime = runtime_years.as_str()
+                    .and_then(|y| Decimal::from_str(y.trim()).ok())
+                    .map(|y| serde_json::Value::from(Duration::from_years(y).months()))
+                    .unwrap_or(serde_json::Value::Null);
+                loan.insert(String::from("runtime"), runtime);
+            }
+        }
+        loans.into_iter().map(serde_json::from_value).collect()
+    }
+
+    fn simulation_params(&self) -> Result<SimulationParams, Vec<FieldErr>> {
+        let mut errors = FieldErrors::default();
+        let simulation = SimulationParams {
+            model: self.rate_model.clone(),
+            volatility: errors.required(LoanFormData::RateVolatility, &self.rate_volatility),
+            runs: errors.optional(LoanFormData::SimulationRuns, &self.simulation_runs).unwrap_or(1000),
+            seed: 1,
+        };
+        if errors.0.is_empty() {
+            Ok(simulation)
+        }else {
+            Err(errors.0)
+        }
     }
 }
 
 impl LoanView {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: String, locale: NumberLocale) -> Self {
         let form = Self::get_form(name.clone(), None);
-        Self {
+        let mut view = Self {
             state: LoanViewState {
                 form,
                 ..Default::default()
@@ -102,62 +857,247 @@ impl LoanView {
                 name,
                 ..LoanViewData::default()
             },
-            result: None
-        }
+            result: None,
+            sensitivity: None,
+            locale,
+        };
+        view.update_form_visibility();
+        view
     }
 
     fn get_form(name: String, data: Option<&LoanViewData>) -> form::Form<LoanFormData> {
         form::Form::new()
             .push(LoanFormData::Name,"Name", Some(name))
-            .push(LoanFormData::Amount, "Amount", data.map(|d|d.amount.clone()))
-            .push(LoanFormData::InterestRate, "Interest rate", data.map(|d| d.interest_rate.clone()))
-            .push(LoanFormData::ClearanceRate,"Clearance rate", data.map(|d| d.clearance_rate.clone()))
-            .push(LoanFormData::RuntimeYears,"Runtime", data.map(|d| d.runtime_years.clone()))
+            .push(LoanFormData::Amount, "Amount", None)
+            .push(LoanFormData::InterestRate, "Interest rate", None)
+            .push(LoanFormData::ClearanceRate,"Clearance rate", None)
+            .push(LoanFormData::RuntimeYears,"Runtime", None)
+            .push(LoanFormData::StartDate,"Start date (optional), e.g. 2020-06-01", data.map(|d| d.start_date.clone()))
+            .push(LoanFormData::PaymentDay,"Payment day (optional)", data.map(|d| d.payment_day.clone()))
+            .push(LoanFormData::RepaymentFreeMonths,"Repayment-free months (optional)", data.map(|d| d.repayment_free_months.clone()))
+            .push(LoanFormData::RepaymentGrant,"Repayment grant (optional), e.g. 36: 5000 or 36: 15%", data.map(|d| d.repayment_grant.clone()))
+            .push(LoanFormData::FixedPeriodYears,"Fixed interest period (optional)", data.map(|d| d.fixed_period_years.clone()))
+            .push(LoanFormData::FollowUpRate,"Follow-up interest rate (optional)", data.map(|d| d.follow_up_rate.clone()))
+            .push(LoanFormData::FollowUpClearanceRate,"Follow-up clearance rate (optional)", data.map(|d| d.follow_up_clearance_rate.clone()))
+            .push(LoanFormData::RateVolatility,"Follow-up rate volatility in %-points (simulation)", data.map(|d| d.rate_volatility.clone()))
+            .push(LoanFormData::SimulationRuns,"Simulation runs (optional)", data.map(|d| d.simulation_runs.clone()))
+            .push(LoanFormData::RateChanges,"Rate changes (optional), e.g. 24: 4.5; 60: 5", data.map(|d| d.rate_changes.clone()))
+            .push(LoanFormData::SpecialRepayments,"Special repayments (optional), e.g. 12: 5000; 24: 5%/y", data.map(|d| d.special_repayments.clone()))
+            .push(LoanFormData::SpecialRepaymentCap,"Special repayment cap % p.a. (optional)", data.map(|d| d.special_repayment_cap.clone()))
+            .push(LoanFormData::EarlyRepayment,"Early repayment (optional), month or date", data.map(|d| d.early_repayment.clone()))
+            .push(LoanFormData::ReinvestmentRate,"Reinvestment rate % p.a.", data.map(|d| d.reinvestment_rate.clone()))
+            .push(LoanFormData::Disbursements,"Disbursements (optional), e.g. 1: 80000; 2021-03-01: 120000", data.map(|d| d.disbursements.clone()))
+            .push(LoanFormData::CommitmentRate,"Commitment interest % p.a. (optional)", data.map(|d| d.commitment_rate.clone()))
+            .push(LoanFormData::CommitmentFreeMonths,"Commitment-free months (optional)", data.map(|d| d.commitment_free_months.clone()))
+            .push(LoanFormData::MonthlyDeposit,"Monthly deposit", data.map(|d| d.monthly_deposit.clone()))
+            .push(LoanFormData::DepositRate,"Deposit interest rate (optional)", data.map(|d| d.deposit_rate.clone()))
+            .push(LoanFormData::ContractFee,"Contract fee % of contract sum (optional)", data.map(|d| d.contract_fee.clone()))
+            .push(LoanFormData::MinimumBalance,"Minimum balance % of contract sum (optional)", data.map(|d| d.minimum_balance.clone()))
+            .push(LoanFormData::MinimumRating,"Minimum rating (optional)", data.map(|d| d.minimum_rating.clone()))
+            .push(LoanFormData::VehicleContribution,"Monthly contribution", data.map(|d| d.vehicle_contribution.clone()))
+            .push(LoanFormData::VehicleReturnRate,"Return rate % p.a. (optional)", data.map(|d| d.vehicle_return_rate.clone()))
+            .push(LoanFormData::VehicleFee,"Fee % (optional)", data.map(|d| d.vehicle_fee.clone()))
+            .push(LoanFormData::Budget,"Payment or budget per period (goal seek)", data.map(|d| d.budget.clone()))
+            .push(LoanFormData::TargetRemaining,"Target remaining debt (goal seek, optional)", data.map(|d| d.target_remaining.clone()))
+            .push(LoanFormData::ProcessingFee,"Processing fee (optional)", data.map(|d| d.processing_fee.clone()))
+            .push(LoanFormData::Disagio,"Disagio % (optional)", data.map(|d| d.disagio.clone()))
+            .push(LoanFormData::AccountFee,"Account fee per month (optional)", data.map(|d| d.account_fee.clone()))
+            .push(LoanFormData::Insurance,"Insurance per month (optional)", data.map(|d| d.insurance.clone()))
     }
 
-    pub fn new_with_data(data: LoanViewData) -> Self {
+    pub fn new_with_data(data: LoanViewData, locale: NumberLocale) -> Self {
         let form = Self::get_form(data.name.clone(), Some(&data));
-        Self {
+        let mut view = Self {
             state: LoanViewState {
                 form,
                 ..LoanViewState::default()
             },
             data,
-            result: None
-        }
+            result: None,
+            sensitivity: None,
+            locale,
+        };
+        view.update_form_visibility();
+        view.update_form_values();
+        view
     }
-    pub fn update(&mut self, message: LoanViewMessage) {
+
+    pub fn set_locale(&mut self, locale: NumberLocale) {
+        self.locale = locale;
+        self.update_form_values();
+    }
+
+    /// Shows the numeric values in the number locale.
+    fn update_form_values(&mut self) {
+        let data = &mut self.data;
+        let locale = self.locale;
+        self.state.form.update_values(|id| match data.typed_input(*id, locale) {
+            Some(text) => Some(text),
+            None => match data.field_mut(*id) {
+                Some(value) if id.is_numeric() => Some(locale.format(value)),
+                Some(value) if id.is_entry_list() => Some(map_entry_numbers(value, |n| locale.format(n))),
+                _ => None,
+            },
+        });
+    }
+
+    fn update_form_visibility(&mut self) {
+        let data = &self.data;
+        self.state.form.update_visibility(|id| id.is_used_by(data));
+    }
+    pub fn update(&mut self, message: LoanViewMessage) -> Command<LoanViewMessage> {
+        let changes_input = match &message {
+            LoanViewMessage::LoanForm(FormMessage::TextInputMessage(id, _idx, FormTextInputMessage::InputChanged(_))) => *id != LoanFormData::Name,
+            LoanViewMessage::ChangeTypeToAnnuity |
+            LoanViewMessage::ChangeTypeToBuildingSavings |
+            LoanViewMessage::ChangeTypeToLinear |
+            LoanViewMessage::ChangeTypeToBullet |
+            LoanViewMessage::ChangeRepaymentVehicle(_) |
+            LoanViewMessage::ChangeAnnuityModeToClearanceRate |
+            LoanViewMessage::ChangeAnnuityModeToRuntime |
+            LoanViewMessage::ChangeRateChangeModeToKeepPayment |
+            LoanViewMessage::ChangeRateChangeModeToRecomputePayment |
+            LoanViewMessage::ChangePaymentFrequency(_) |
+            LoanViewMessage::ChangeDayCount(_) |
+            LoanViewMessage::ChangeInterestTiming(_) |
+            LoanViewMessage::ChangeRoundingMode(_) |
+            LoanViewMessage::ApplyGoalSeek => true,
+            _ => false,
+        };
         match message {
             LoanViewMessage::Calc => {
-                self.result.take();
-                let result = match self.data.loan_type {
-                    LoanType::Annuity => self.calc_annuity(),
-                    LoanType::BuildingSavings => self.calc_building_saving(),
-                };
-                if let Ok(result) = result {
-                    self.result = Some(result);
+                return self.calc();
+            }
+            LoanViewMessage::InputPaused(generation) => {
+                if generation == self.state.generation {
+                    if let Ok(params) = self.data.params() {
+                        return LoanView::update_sensitivity(params, generation);
+                    }
+                }
+            }
+            LoanViewMessage::SensitivityDone(generation, sensitivity) => {
+                if generation == self.state.generation {
+                    self.sensitivity = sensitivity;
+                    self.state.sensitivity_generation = generation;
                 }
             }
             LoanViewMessage::ChangeTypeToAnnuity => {
                 self.data.loan_type = LoanType::Annuity;
+                self.update_form_visibility();
             }
             LoanViewMessage::ChangeTypeToBuildingSavings => {
                 self.data.loan_type = LoanType::BuildingSavings;
+                self.update_form_visibility();
+            }
+            LoanViewMessage::ChangeTypeToLinear => {
+                self.data.loan_type = LoanType::Linear;
+                self.update_form_visibility();
+            }
+            LoanViewMessage::ChangeTypeToBullet => {
+                self.data.loan_type = LoanType::Bullet;
+                self.update_form_visibility();
+            }
+            LoanViewMessage::ChangeRepaymentVehicle(kind) => {
+                self.data.repayment_vehicle = kind;
+                self.update_form_visibility();
+            }
+            LoanViewMessage::ChangeAnnuityModeToClearanceRate => {
+                self.data.annuity_mode = AnnuityMode::ClearanceRate;
+                self.update_form_visibility();
+            }
+            LoanViewMessage::ChangeAnnuityModeToRuntime => {
+                self.data.annuity_mode = AnnuityMode::Runtime;
+                self.update_form_visibility();
+            }
+            LoanViewMessage::ChangeRateChangeModeToKeepPayment => {
+                self.data.rate_change_mode = RateChangeMode::KeepPayment;
+            }
+            LoanViewMessage::ChangeRateChangeModeToRecomputePayment => {
+                self.data.rate_change_mode = RateChangeMode::RecomputePayment;
+            }
+            LoanViewMessage::ChangePaymentFrequency(frequency) => {
+                self.data.payment_frequency = frequency;
+            }
+            LoanViewMessage::ChangeDayCount(day_count) => {
+                self.data.day_count = day_count;
+            }
+            LoanViewMessage::ChangeInterestTiming(timing) => {
+                self.data.interest_timing = timing;
+            }
+            LoanViewMessage::ChangeRoundingMode(mode) => {
+                self.data.rounding_mode = mode;
+            }
+            LoanViewMessage::ToggleSensitivity => {
+                self.state.show_sensitivity = !self.state.show_sensitivity;
+            }
+            LoanViewMessage::SeekMaxAmount => {
+                self.goal_seek(LoanView::seek_max_amount);
+            }
+            LoanViewMessage::SeekClearanceRate => {
+                self.goal_seek(LoanView::seek_clearance_rate);
+            }
+            LoanViewMessage::SeekInterestRate => {
+                self.goal_seek(LoanView::seek_interest_rate);
+            }
+            LoanViewMessage::ApplyGoalSeek => {
+                match self.state.goal_seek.take() {
+                    Some(GoalSeek::Amount(max)) => {
+                        self.data.annuity_mode = AnnuityMode::ClearanceRate;
+                        self.data.amount = Some(Money(max.amount));
+                        self.data.clearance_rate = Some(Percent(max.clearance_rate));
+                        for &id in &[LoanFormData::Amount, LoanFormData::ClearanceRate] {
+                            self.state.form.set_value(id, self.data.typed_input(id, self.locale).unwrap_or_default());
+                        }
+                        self.update_form_visibility();
+                    }
+                    Some(GoalSeek::ClearanceRate(clearance_rate)) => {
+                        self.data.annuity_mode = AnnuityMode::ClearanceRate;
+                        self.data.clearance_rate = Some(Percent(clearance_rate));
+                        self.state.form.set_value(LoanFormData::ClearanceRate, self.data.typed_input(LoanFormData::ClearanceRate, self.locale).unwrap_or_default());
+                        self.update_form_visibility();
+                    }
+                    Some(GoalSeek::InterestRate(rate)) => {
+                        self.data.annuity_mode = AnnuityMode::Runtime;
+                        self.data.interest_rate = Some(Percent(rate.nominal));
+                        self.state.form.set_value(LoanFormData::InterestRate, self.data.typed_input(LoanFormData::InterestRate, self.locale).unwrap_or_default());
+                        self.update_form_visibility();
+                    }
+                    _ => (),
+                }
+            }
+            LoanViewMessage::ChangeRateModel(model) => {
+                self.data.rate_model = model;
+            }
+            LoanViewMessage::RunSimulation => {
+                match (self.params(), self.data.simulation_params()) {
+                    (Ok(params), Ok(simulation)) => {
+                        self.state.simulating = true;
+                        return Command::perform(LoanView::simulate(params, simulation), LoanViewMessage::SimulationDone);
+                    }
+                    (params, simulation) => {
+                        let errors = params.err().into_iter().chain(simulation.err()).flatten().collect();
+                        self.set_errors(errors, false);
+                    }
+                }
+            }
+            LoanViewMessage::SimulationDone(result) => {
+                self.state.simulating = false;
+                self.state.simulation = Some(result);
             }
             LoanViewMessage::LoanForm(m) => {
                 if let FormMessage::TextInputMessage(i, _idx, FormTextInputMessage::InputChanged(value) ) = &m {
-                    match i {
-                        LoanFormData::Name => self.data.name = value.clone(),
-                        LoanFormData::Amount => self.data.amount = value.clone(),
-                        LoanFormData::InterestRate => self.data.interest_rate = value.clone(),
-                        LoanFormData::ClearanceRate => self.data.clearance_rate = value.clone(),
-                        LoanFormData::RuntimeYears => self.data.runtime_years = value.clone(),
-                        _ => ()
+                    self.data.set_input(*i, value, self.locale);
+                    if matches!(i, LoanFormData::FixedPeriodYears | LoanFormData::Disbursements | LoanFormData::EarlyRepayment) {
+                        self.update_form_visibility();
                     }
                 }
                 self.state.form.update(m);
             },
         }
+        if changes_input {
+            return self.recalc();
+        }
+        Command::none()
     }
 
     pub fn view(&mut self) -> Element<LoanViewMessage> {
@@ -177,27 +1117,317 @@ impl LoanView {
                             .on_press(LoanViewMessage::ChangeTypeToBuildingSavings)
                             .style(ButtonStyle { active: matches!(self.data.loan_type, LoanType::BuildingSavings)})
                     )
-            )
-            .push(Button::new(&mut self.state.calc_button, Text::new("Calc")).on_press(LoanViewMessage::Calc));
+                    .push(
+                        Button::new(&mut self.state.linear_btn, Text::new("Linear"))
+                            .on_press(LoanViewMessage::ChangeTypeToLinear)
+                            .style(ButtonStyle { active: matches!(self.data.loan_type, LoanType::Linear)})
+                    )
+                    .push(
+                        Button::new(&mut self.state.bullet_btn, Text::new("Bullet"))
+                            .on_press(LoanViewMessage::ChangeTypeToBullet)
+                            .style(ButtonStyle { active: matches!(self.data.loan_type, LoanType::Bullet)})
+                    )
+            );
 
-        if let Some(result) = self.result.as_mut() {
-            col = col
-                .push(
-                    Text::new(
-                        format!("Monthly rate: {}\nPaid interest: {}\nRemaining: {}\nCleared: {}",
-                                result.monthly_rate.round_dp(2),
-                                result.overall.paid_interest.round_dp(2),
-                                result.overall.remaining.round_dp(2),
-                                result.overall.cleared_amount.round_dp(2),
-                            )
+        if let LoanType::Annuity = self.data.loan_type {
+            col = col.push(
+                Row::new()
+                    .push(
+                        Button::new(&mut self.state.clearance_rate_mode_btn, Text::new("By clearance rate"))
+                            .on_press(LoanViewMessage::ChangeAnnuityModeToClearanceRate)
+                            .style(ButtonStyle { active: matches!(self.data.annuity_mode, AnnuityMode::ClearanceRate)})
                     )
+                    .push(
+                        Button::new(&mut self.state.runtime_mode_btn, Text::new("By runtime"))
+                            .on_press(LoanViewMessage::ChangeAnnuityModeToRuntime)
+                            .style(ButtonStyle { active: matches!(self.data.annuity_mode, AnnuityMode::Runtime)})
+                    )
+            );
+            if !self.data.rate_changes.trim().is_empty() {
+                col = col.push(
+                    Row::new()
+                        .push(
+                            Button::new(&mut self.state.keep_payment_btn, Text::new("Keep payment"))
+                                .on_press(LoanViewMessage::ChangeRateChangeModeToKeepPayment)
+                                .style(ButtonStyle { active: matches!(self.data.rate_change_mode, RateChangeMode::KeepPayment)})
+                        )
+                        .push(
+                            Button::new(&mut self.state.recompute_payment_btn, Text::new("Recompute payment"))
+                                .on_press(LoanViewMessage::ChangeRateChangeModeToRecomputePayment)
+                                .style(ButtonStyle { active: matches!(self.data.rate_change_mode, RateChangeMode::RecomputePayment)})
+                        )
                 );
+            }
+        }
 
-            let r = result.months.iter_mut().map(|r| {
-                r.view()
-            }).fold(Column::new(), |acc, v| {
-                acc.push(v)
-            });
+        if let LoanType::Bullet = self.data.loan_type {
+            col = col.push(
+                Row::new()
+                    .push(
+                        Button::new(&mut self.state.no_vehicle_btn, Text::new("No repayment vehicle"))
+                            .on_press(LoanViewMessage::ChangeRepaymentVehicle(None))
+                            .style(ButtonStyle { active: self.data.repayment_vehicle.is_none()})
+                    )
+                    .push(
+                        Button::new(&mut self.state.savings_plan_btn, Text::new("Savings plan"))
+                            .on_press(LoanViewMessage::ChangeRepaymentVehicle(Some(RepaymentVehicleKind::SavingsPlan)))
+                            .style(ButtonStyle { active: matches!(self.data.repayment_vehicle, Some(RepaymentVehicleKind::SavingsPlan))})
+                    )
+                    .push(
+                        Button::new(&mut self.state.building_savings_vehicle_btn, Text::new("Building savings contract"))
+                            .on_press(LoanViewMessage::ChangeRepaymentVehicle(Some(RepaymentVehicleKind::BuildingSavings)))
+                            .style(ButtonStyle { active: matches!(self.data.repayment_vehicle, Some(RepaymentVehicleKind::BuildingSavings))})
+                    )
+            );
+        }
+
+        if !matches!(self.data.loan_type, LoanType::BuildingSavings) {
+            col = col.push(
+                Row::new()
+                    .push(
+                        Button::new(&mut self.state.monthly_btn, Text::new("Monthly"))
+                            .on_press(LoanViewMessage::ChangePaymentFrequency(PaymentFrequency::Monthly))
+                            .style(ButtonStyle { active: self.data.payment_frequency == PaymentFrequency::Monthly})
+                    )
+                    .push(
+                        Button::new(&mut self.state.quarterly_btn, Text::new("Quarterly"))
+                            .on_press(LoanViewMessage::ChangePaymentFrequency(PaymentFrequency::Quarterly))
+                            .style(ButtonStyle { active: self.data.payment_frequency == PaymentFrequency::Quarterly})
+                    )
+                    .push(
+                        Button::new(&mut self.state.semi_annual_btn, Text::new("Semi-annual"))
+                            .on_press(LoanViewMessage::ChangePaymentFrequency(PaymentFrequency::SemiAnnual))
+                            .style(ButtonStyle { active: self.data.payment_frequency == PaymentFrequency::SemiAnnual})
+                    )
+                    .push(
+                        Button::new(&mut self.state.annual_btn, Text::new("Annual"))
+                            .on_press(LoanViewMessage::ChangePaymentFrequency(PaymentFrequency::Annual))
+                            .style(ButtonStyle { active: self.data.payment_frequency == PaymentFrequency::Annual})
+                    )
+            ).push(
+                Row::new()
+                    .push(
+                        Button::new(&mut self.state.thirty_360_btn, Text::new("30/360"))
+                            .on_press(LoanViewMessage::ChangeDayCount(DayCount::Thirty360))
+                            .style(ButtonStyle { active: self.data.day_count == DayCount::Thirty360})
+                    )
+                    .push(
+                        Button::new(&mut self.state.act_360_btn, Text::new("act/360"))
+                            .on_press(LoanViewMessage::ChangeDayCount(DayCount::Act360))
+                            .style(ButtonStyle { active: self.data.day_count == DayCount::Act360})
+                    )
+                    .push(
+                        Button::new(&mut self.state.act_365_btn, Text::new("act/365"))
+                            .on_press(LoanViewMessage::ChangeDayCount(DayCount::Act365))
+                            .style(ButtonStyle { active: self.data.day_count == DayCount::Act365})
+                    )
+            ).push(
+                Row::new()
+                    .push(
+                        Button::new(&mut self.state.in_arrears_btn, Text::new("Interest in arrears"))
+                            .on_press(LoanViewMessage::ChangeInterestTiming(InterestTiming::InArrears))
+                            .style(ButtonStyle { active: self.data.interest_timing == InterestTiming::InArrears})
+                    )
+                    .push(
+                        Button::new(&mut self.state.in_advance_btn, Text::new("Interest in advance"))
+                            .on_press(LoanViewMessage::ChangeInterestTiming(InterestTiming::InAdvance))
+                            .style(ButtonStyle { active: self.data.interest_timing == InterestTiming::InAdvance})
+                    )
+            );
+        }
+
+        col = col.push(
+            Row::new()
+                .push(
+                    Button::new(&mut self.state.half_up_btn, Text::new("Round half up"))
+                        .on_press(LoanViewMessage::ChangeRoundingMode(RoundingMode::HalfUp))
+                        .style(ButtonStyle { active: self.data.rounding_mode == RoundingMode::HalfUp})
+                )
+                .push(
+                    Button::new(&mut self.state.half_even_btn, Text::new("Banker's rounding"))
+                        .on_press(LoanViewMessage::ChangeRoundingMode(RoundingMode::HalfEven))
+                        .style(ButtonStyle { active: self.data.rounding_mode == RoundingMode::HalfEven})
+                )
+                .push(
+                    Button::new(&mut self.state.round_down_btn, Text::new("Round down"))
+                        .on_press(LoanViewMessage::ChangeRoundingMode(RoundingMode::Down))
+                        .style(ButtonStyle { active: self.data.rounding_mode == RoundingMode::Down})
+                )
+                .push(
+                    Button::new(&mut self.state.round_up_btn, Text::new("Round up"))
+                        .on_press(LoanViewMessage::ChangeRoundingMode(RoundingMode::Up))
+                        .style(ButtonStyle { active: self.data.rounding_mode == RoundingMode::Up})
+                )
+        );
+
+        let mut actions = Row::new()
+            .push(Button::new(&mut self.state.calc_button, Text::new("Calc")).on_press(LoanViewMessage::Calc));
+        if self.sensitivity.is_some() {
+            actions = actions.push(
+                Button::new(&mut self.state.sensitivity_btn, Text::new("Sensitivity"))
+                    .on_press(LoanViewMessage::ToggleSensitivity)
+                    .style(ButtonStyle { active: self.state.show_sensitivity})
+            );
+        }
+        col = col.push(actions);
+        if let Some(error) = &self.state.error {
+            col = col.push(Text::new(error));
+        }
+        if self.state.stale {
+            col = col.push(Text::new("Outdated result, the inputs are invalid"));
+        }
+
+        if let LoanType::Annuity = self.data.loan_type {
+            let mut goal_seek = Row::new()
+                .spacing(4)
+                .push(Button::new(&mut self.state.max_amount_btn, Text::new("Max amount")).on_press(LoanViewMessage::SeekMaxAmount))
+                .push(Button::new(&mut self.state.clearance_rate_btn, Text::new("Required clearance rate")).on_press(LoanViewMessage::SeekClearanceRate))
+                .push(Button::new(&mut self.state.implied_rate_btn, Text::new("Implied rate")).on_press(LoanViewMessage::SeekInterestRate));
+            let text = match &self.state.goal_seek {
+                Some(GoalSeek::Amount(max)) => Some(format!("Max amount: {} with {} % clearance rate", max.amount, max.clearance_rate)),
+                Some(GoalSeek::ClearanceRate(clearance_rate)) => Some(format!("Required clearance rate: {} %", clearance_rate)),
+                Some(GoalSeek::InterestRate(rate)) => Some(format!("Implied rate: {} % nominal, {} effective",
+                                                                   rate.nominal,
+                                                                   rate.effective.map(|r| format!("{} %", r.round_dp(2))).unwrap_or(String::from("-")))),
+                Some(GoalSeek::NotReachable) => Some(String::from("Goal not reachable")),
+                None => None,
+            };
+            if let Some(text) = text {
+                goal_seek = goal_seek.push(Text::new(text));
+                if !matches!(self.state.goal_seek, Some(GoalSeek::NotReachable)) {
+                    goal_seek = goal_seek.push(Button::new(&mut self.state.apply_goal_seek_btn, Text::new("Apply")).on_press(LoanViewMessage::ApplyGoalSeek));
+                }
+            }
+            col = col.push(goal_seek);
+        }
+
+        if LoanFormData::RateVolatility.is_used_by(&self.data) {
+            let mut simulate_btn = Button::new(&mut self.state.simulate_btn, Text::new("Simulate follow-up rates"));
+            if !self.state.simulating {
+                simulate_btn = simulate_btn.on_press(LoanViewMessage::RunSimulation);
+            }
+            col = col.push(
+                Row::new()
+                    .spacing(4)
+                    .push(
+                        Button::new(&mut self.state.normal_model_btn, Text::new("Normal distribution"))
+                            .on_press(LoanViewMessage::ChangeRateModel(RateModel::Normal))
+                            .style(ButtonStyle { active: self.data.rate_model == RateModel::Normal})
+                    )
+                    .push(
+                        Button::new(&mut self.state.random_walk_btn, Text::new("Random walk"))
+                            .on_press(LoanViewMessage::ChangeRateModel(RateModel::RandomWalk))
+                            .style(ButtonStyle { active: self.data.rate_model == RateModel::RandomWalk})
+                    )
+                    .push(simulate_btn)
+            );
+            let text = match &self.state.simulation {
+                _ if self.state.simulating => Some(String::from("Simulating...")),
+                Some(Ok(simulation)) => Some(simulation_summary(simulation)),
+                Some(Err(e)) => Some(format!("Simulation failed: {:?}", e)),
+                None => None,
+            };
+            if let Some(text) = text {
+                col = col.push(Text::new(text));
+            }
+        }
+
+        if let Some(result) = self.result.as_mut() {
+            let rate_label = match (&self.data.loan_type, &self.data.payment_frequency) {
+                (LoanType::BuildingSavings, _) | (_, PaymentFrequency::Monthly) => "Monthly rate",
+                (_, PaymentFrequency::Quarterly) => "Quarterly rate",
+                (_, PaymentFrequency::SemiAnnual) => "Semi-annual rate",
+                (_, PaymentFrequency::Annual) => "Annual rate",
+            };
+            let mut summary = format!("{}: {}\nClearance rate: {} %\nPaid interest: {}\nRemaining: {}\nCleared: {}\nPaid off: {}",
+                                      rate_label,
+                                      result.monthly_rate.round_dp(2),
+                                      result.clearance_rate.round_dp(2),
+                                      result.overall.paid_interest.round_dp(2),
+                                      result.overall.remaining.round_dp(2),
+                                      result.overall.cleared_amount.round_dp(2),
+                                      result.payoff_month.map(|m| match result.overall.date {
+                                          Some(date) => format!("month {} ({})", m, date),
+                                          None => format!("month {}", m),
+                                      }).unwrap_or(String::from("-")),
+            );
+            if let Some(effective_rate) = result.effective_rate {
+                summary.push_str(&format!("\nEffective rate: {} %", effective_rate.round_dp(2)));
+            }
+            if result.upfront_fees > Decimal::new(0, 2) || result.overall.fees > Decimal::new(0, 2) {
+                summary.push_str(&format!("\nFees: {}\nUpfront fees: {}",
+                                          result.overall.fees.round_dp(2),
+                                          result.upfront_fees.round_dp(2),
+                ));
+            }
+            if let Some(repayment_free_monthly_rate) = result.repayment_free_monthly_rate {
+                summary.push_str(&format!("\nRepayment-free monthly rate: {}", repayment_free_monthly_rate.round_dp(2)));
+            }
+            if let Some(fixed_period_remaining) = result.fixed_period_remaining {
+                summary.push_str(&format!("\nRemaining after fixed period: {}", fixed_period_remaining.round_dp(2)));
+            }
+            if let Some(follow_up_monthly_rate) = result.follow_up_monthly_rate {
+                summary.push_str(&format!("\nFollow-up monthly rate: {}", follow_up_monthly_rate.round_dp(2)));
+            }
+            if let Some(allotment) = &result.building_savings {
+                summary.push_str(&format!("\nAllotment: month {}\nBalance at allotment: {}\nBuilding loan: {}\nLoan monthly rate: {}",
+                                          allotment.allotment_month,
+                                          allotment.balance.round_dp(2),
+                                          allotment.loan_amount.round_dp(2),
+                                          allotment.loan_monthly_rate.round_dp(2),
+                ));
+            }
+            if result.overall.earned_interest > Decimal::new(0, 2) {
+                summary.push_str(&format!("\nEarned interest: {}", result.overall.earned_interest.round_dp(2)));
+            }
+            if let Some(maturity) = &result.maturity {
+                summary.push_str(&format!("\nVehicle balance at maturity: {}\nSurplus: {}",
+                                          maturity.vehicle_balance.round_dp(2),
+                                          maturity.surplus.round_dp(2),
+                ));
+                if maturity.shortfall > Decimal::new(0, 2) {
+                    summary.push_str(&format!("\nShortfall at maturity: {}", maturity.shortfall.round_dp(2)));
+                }
+            }
+            if result.overall.grant > Decimal::new(0, 2) {
+                summary.push_str(&format!("\nRepayment grant: {}\nCost after grant: {}",
+                                          result.overall.grant.round_dp(2),
+                                          result.cost().round_dp(2),
+                ));
+            }
+            if let Some(early) = &result.early_repayment {
+                summary.push_str(&format!("\nEarly repayment: month {}{}\nRemaining debt then: {}\nPenalty estimate: {}",
+                                          early.month,
+                                          early.date.map(|d| format!(" ({})", d)).unwrap_or_default(),
+                                          early.remaining.round_dp(2),
+                                          early.penalty.round_dp(2),
+                ));
+            }
+            if let Some(construction) = &result.construction {
+                summary.push_str(&format!("\nConstruction: {} months\nInterest during construction: {}\nCommitment interest: {}",
+                                          construction.months,
+                                          construction.interest.round_dp(2),
+                                          construction.commitment_interest.round_dp(2),
+                ));
+            }
+            if let Some(savings) = &result.special_repayment_savings {
+                summary.push_str(&format!("\nSpecial repayments: {}\nSaved interest: {}\nSaved months: {}",
+                                          result.overall.special_repayment.round_dp(2),
+                                          savings.interest.round_dp(2),
+                                          savings.months.map(|m| m.to_string()).unwrap_or_else(|| String::from("n/a")),
+                ));
+            }
+            col = col.push(Text::new(summary));
+
+            let r = match &self.sensitivity {
+                Some(grid) if self.state.show_sensitivity && self.state.sensitivity_generation != self.state.generation => {
+                    Column::new()
+                        .push(Text::new("Outdated sensitivity, it does not match the inputs"))
+                        .push(sensitivity_view(grid))
+                }
+                Some(grid) if self.state.show_sensitivity => sensitivity_view(grid),
+                _ => schedule_view(&result.months),
+            };
 
             col = col.push(
                 Scrollable::new(&mut self.state.result_scroller)
@@ -212,78 +1442,193 @@ impl LoanView {
 }
 
 impl LoanView {
-    pub fn calc(&mut self) {
-        let r = match self.data.loan_type {
-            LoanType::Annuity => self.calc_annuity(),
-            LoanType::BuildingSavings => self.calc_building_saving()
-        };
-        if let Ok(r) = r {
-            self.result = Some(r);
+    /// Parameters for a goal seek, which may solve for the amount, clearance or interest rate left empty.
+    fn goal_seek_params(&self) -> Result<(LoanParams, Decimal), Vec<FieldErr>> {
+        let mut data = self.data.clone();
+        data.amount.get_or_insert(Money::default());
+        data.clearance_rate.get_or_insert(Percent::default());
+        data.interest_rate.get_or_insert(Percent::default());
+        let mut errors = FieldErrors::default();
+        let target_remaining = errors.optional(LoanFormData::TargetRemaining, &self.data.target_remaining).unwrap_or_default();
+        match data.params().map_err(|e| self.input_errors(e)) {
+            Ok(params) if errors.0.is_empty() => Ok((params, target_remaining)),
+            params => Err(params.err().into_iter().flatten().chain(errors.0).collect()),
         }
     }
-    fn calc_building_saving(&mut self) -> Result<CalcResultOverview, Box<dyn Error>> {
-        let mut result = CalcResultOverview::default();
 
-        let amount = self.data.amount.parse::<Decimal>()?;
-        let interest_rate = Decimal::from_str(&self.data.interest_rate)? / dec!(100);
-        let clearance_rate = Decimal::from_str(&self.data.clearance_rate)? / dec!(100);
-        result.monthly_rate = amount * (interest_rate + clearance_rate ) / dec!(12);
-        let runtime = self.data.runtime_years.parse::<i32>()?;
+    /// Budget or payment per period of a goal seek.
+    fn goal_seek_budget(&self) -> Result<Decimal, Vec<FieldErr>> {
+        let mut errors = FieldErrors::default();
+        let budget = errors.required(LoanFormData::Budget, &self.data.budget);
+        if errors.0.is_empty() {
+            Ok(budget)
+        }else {
+            Err(errors.0)
+        }
+    }
 
-        for month in 0..=(runtime * 12) {
-            let paid_interest_month = amount * interest_rate / dec!(12) as Decimal;
-            result.overall.paid_interest += paid_interest_month;
+    fn seek_max_amount(&self) -> Result<GoalSeek, Vec<FieldErr>> {
+        let (params, target_remaining) = self.goal_seek_params()?;
+        let budget = self.goal_seek_budget()?;
+        goal_seek_result(calc::max_amount(&params, budget, target_remaining).map(GoalSeek::Amount))
+    }
 
-            let saved_month = result.monthly_rate - paid_interest_month;
-            result.overall.cleared_amount += saved_month;
+    fn seek_clearance_rate(&self) -> Result<GoalSeek, Vec<FieldErr>> {
+        let (params, target_remaining) = self.goal_seek_params()?;
+        goal_seek_result(calc::required_clearance_rate(&params, target_remaining).map(GoalSeek::ClearanceRate))
+    }
 
-            let remaining = amount - result.overall.cleared_amount;
+    fn seek_interest_rate(&self) -> Result<GoalSeek, Vec<FieldErr>> {
+        let (params, _) = self.goal_seek_params()?;
+        let payment = self.goal_seek_budget()?;
+        goal_seek_result(calc::implied_interest_rate(&params, payment).map(GoalSeek::InterestRate))
+    }
 
-            result.months.push(Box::new(CalcResult {
-                month: month + 1,
-                remaining: remaining.round_dp(2),
-                cleared_amount: saved_month.round_dp(2),
-                paid_interest: paid_interest_month.round_dp(2)
-            }));
+    /// Shows the result of a goal seek, or the errors of its inputs.
+    fn goal_seek(&mut self, seek: fn(&Self) -> Result<GoalSeek, Vec<FieldErr>>) {
+        self.set_errors(vec![], false);
+        match seek(self) {
+            Ok(goal_seek) => self.state.goal_seek = Some(goal_seek),
+            Err(errors) => {
+                self.state.goal_seek = None;
+                self.set_errors(errors, false);
+            }
+        }
+    }
+
+    async fn simulate(params: LoanParams, simulation: SimulationParams) -> Result<SimulationResult, CalcErr> {
+        calc::simulate(&params, &simulation)
+    }
 
+    pub fn calc(&mut self) -> Command<LoanViewMessage> {
+        self.state.generation += 1;
+        match self.calc_result(false) {
+            Some(params) => LoanView::update_sensitivity(params, self.state.generation),
+            None => Command::none(),
         }
+    }
 
-        if let Some(last) = result.months.last() {
-            result.overall.remaining = last.remaining.round_dp(2);
+    /// Computes the sensitivity grid in the background.
+    fn update_sensitivity(params: LoanParams, generation: u64) -> Command<LoanViewMessage> {
+        Command::perform(
+            LoanView::sensitivity(params, generation),
+            |(generation, sensitivity)| LoanViewMessage::SensitivityDone(generation, sensitivity),
+        )
+    }
+
+    /// Recalculates the schedule after an input change. The sensitivity grid follows once
+    /// typing pauses.
+    fn recalc(&mut self) -> Command<LoanViewMessage> {
+        self.state.generation += 1;
+        if self.calc_result(true).is_none() {
+            return Command::none();
         }
-        Ok(result)
+        Command::perform(LoanView::pause(self.state.generation), LoanViewMessage::InputPaused)
     }
 
-    fn calc_annuity(&mut self) -> Result<CalcResultOverview, Box<dyn Error>> {
-        let mut result = CalcResultOverview::default();
+    /// Resolves after the pause without holding a thread of the executor.
+    async fn pause(generation: u64) -> u64 {
+        let (sender, receiver) = iced_native::futures::channel::oneshot::channel();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(400));
+            let _ = sender.send(());
+        });
+        let _ = receiver.await;
+        generation
+    }
 
-        let amount = self.data.amount.parse::<Decimal>()?;
-        let interest_rate = Decimal::from_str(&self.data.interest_rate)? / dec!(100);
-        let clearance_rate = Decimal::from_str(&self.data.clearance_rate)? / dec!(100);
-        result.monthly_rate = amount * (interest_rate + clearance_rate ) / dec!(12);
-        let runtime = self.data.runtime_years.parse::<i32>()?;
+    async fn sensitivity(params: LoanParams, generation: u64) -> (u64, Option<SensitivityGrid>) {
+        (generation, sensitivity_grid(&params))
+    }
 
-        let mut remaining = amount;
-        for month in 0..=(runtime * 12) {
-            let paid_interest_month = remaining * interest_rate / dec!(12) as Decimal;
-            result.overall.paid_interest += paid_interest_month;
+    /// Calculates the result, or marks the previous one as stale and shows the errors. Empty
+    /// required inputs are not reported while typing.
+    fn calc_result(&mut self, live: bool) -> Option<LoanParams> {
+        self.set_errors(vec![], live);
+        let errors = match self.params() {
+            Ok(params) => match calc::calc(&params) {
+                Ok(result) => {
+                    self.result = Some(result);
+                    self.state.stale = false;
+                    return Some(params);
+                }
+                Err(e) => match calc_field_errors(&e) {
+                    Some(errors) => errors,
+                    None => {
+                        self.state.error = Some(format!("Calculation failed: {:?}", e));
+                        vec![]
+                    }
+                },
+            },
+            Err(errors) => errors,
+        };
+        self.set_errors(errors, live);
+        self.state.stale = self.result.is_some();
+        None
+    }
 
-            let cleared_month = result.monthly_rate - paid_interest_month;
-            result.overall.cleared_amount += cleared_month;
+    /// Parameters of the loan. A typed field without value but with input text did not parse.
+    fn params(&self) -> Result<LoanParams, Vec<FieldErr>> {
+        self.data.params().map_err(|errors| self.input_errors(errors))
+    }
 
-            result.months.push(Box::new(CalcResult {
-                month: month + 1,
-                remaining: remaining.round_dp(2),
-                cleared_amount: cleared_month.round_dp(2),
-                paid_interest: paid_interest_month.round_dp(2)
-            }));
+    fn input_errors(&self, errors: Vec<FieldErr>) -> Vec<FieldErr> {
+        errors.into_iter().map(|e| match self.state.form.value(e.field) {
+            Some(text) if e.missing && !text.trim().is_empty() => FieldErr {
+                field: e.field,
+                message: format!("Invalid value '{}'", text.trim()),
+                missing: false,
+            },
+            _ => e,
+        }).collect()
+    }
 
-            remaining -= cleared_month;
+    /// Shows the errors below their inputs, or below the actions if the input is hidden.
+    fn set_errors(&mut self, mut errors: Vec<FieldErr>, live: bool) {
+        if live {
+            errors.retain(|e| !e.missing);
         }
+        let data = &self.data;
+        self.state.form.update_errors(|id| {
+            errors.iter().find(|e| e.field == *id).map(|e| e.message.clone())
+        });
+        let hidden = errors.iter()
+            .filter(|e| !e.field.is_used_by(data))
+            .map(|e| format!("{:?}: {}", e.field, e.message))
+            .collect::<Vec<String>>();
+        self.state.error = if hidden.is_empty() {
+            None
+        }else {
+            Some(format!("Invalid input: {}", hidden.join(", ")))
+        };
+    }
+}
 
-        if let Some(last) = result.months.last() {
-            result.overall.remaining = last.remaining.round_dp(2);
-        }
-        Ok(result)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn from_file_migrates_text_inputs() {
+        let old = br#"[
+            {"name": "A", "amount": "100000", "interest_rate": " 3.5", "clearance_rate": "2", "runtime_years": "10.5", "loan_type": "Annuity"},
+            {"name": "B", "amount": "", "interest_rate": "x", "clearance_rate": "", "runtime_years": "", "loan_type": "Linear", "processing_fee": "500"}
+        ]"#;
+        let loans = LoanViewData::from_file(old).unwrap();
+        assert_eq!(loans[0].amount, Some(Money(dec!(100000))));
+        assert_eq!(loans[0].interest_rate, Some(Percent(dec!(3.5))));
+        assert_eq!(loans[0].clearance_rate, Some(Percent(dec!(2))));
+        assert_eq!(loans[0].runtime, Some(Duration(126)));
+        assert_eq!(loans[1].amount, None);
+        assert_eq!(loans[1].interest_rate, None);
+        assert_eq!(loans[1].runtime, None);
+        assert_eq!(loans[1].processing_fee, "500");
+        assert!(matches!(loans[1].loan_type, LoanType::Linear));
+
+        // Files in the current format load unchanged.
+        let json = serde_json::to_string(&loans).unwrap();
+        let again = LoanViewData::from_file(json.as_bytes()).unwrap();
+        assert_eq!(serde_json::to_string(&again).unwrap(), json);
     }
-}
\ No newline at end of file
+}
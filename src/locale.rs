@@ -0,0 +1,145 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Decimal and thousands separators of number input. Values are stored with a `.` as
+/// decimal separator and no grouping regardless of the locale.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NumberLocale {
+    /// `1,234.5`
+    English,
+    /// `1.234,5`
+    German,
+}
+
+impl Default for NumberLocale {
+    fn default() -> Self {
+        NumberLocale::English
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ParseNumberErr {
+    Empty,
+    /// More than one decimal separator.
+    MultipleDecimalSeparators,
+    /// Thousands separators not between groups of three digits.
+    InvalidGrouping,
+    InvalidNumber,
+}
+
+impl NumberLocale {
+    /// German if the `LANG` environment variable names a German locale.
+    pub fn from_env() -> Self {
+        match std::env::var("LANG") {
+            Ok(lang) if lang.starts_with("de") => NumberLocale::German,
+            _ => NumberLocale::English,
+        }
+    }
+
+    /// Decimal and thousands separator.
+    fn separators(&self) -> (char, char) {
+        match self {
+            NumberLocale::English => ('.', ','),
+            NumberLocale::German => (',', '.'),
+        }
+    }
+
+    /// Parses a number with optional thousands separators, spaces, a currency symbol and a
+    /// trailing `%`.
+    pub fn parse(&self, value: &str) -> Result<Decimal, ParseNumberErr> {
+        let (decimal_separator, thousands_separator) = self.separators();
+        let value = value.trim().trim_end_matches('%');
+        let value = value.trim_start_matches('€').trim_end_matches('€').trim()
+            .trim_start_matches("EUR").trim_end_matches("EUR")
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        if value.is_empty() {
+            return Err(ParseNumberErr::Empty);
+        }
+
+        let mut parts = value.split(decimal_separator);
+        let integer = parts.next().unwrap_or("");
+        let fraction = parts.next();
+        if parts.next().is_some() {
+            return Err(ParseNumberErr::MultipleDecimalSeparators);
+        }
+        let (sign, integer) = match integer.strip_prefix('-') {
+            Some(integer) => ("-", integer),
+            None => ("", integer),
+        };
+        let groups = integer.split(thousands_separator).collect::<Vec<&str>>();
+        if groups.len() > 1 && (groups[0].is_empty() || groups[0].len() > 3 || groups[1..].iter().any(|g| g.len() != 3)) {
+            return Err(ParseNumberErr::InvalidGrouping);
+        }
+        let normalized = match fraction {
+            Some(fraction) => format!("{}{}.{}", sign, groups.concat(), fraction),
+            None => format!("{}{}", sign, groups.concat()),
+        };
+        Decimal::from_str(&normalized).map_err(|_| ParseNumberErr::InvalidNumber)
+    }
+
+    /// Normalises input for storage, leaving it unchanged if it does not parse.
+    pub fn normalize(&self, value: &str) -> String {
+        match self.parse(value) {
+            Ok(number) => number.to_string(),
+            Err(_) => value.to_owned(),
+        }
+    }
+
+    /// Formats a stored value for input.
+    pub fn format(&self, value: &str) -> String {
+        match self {
+            NumberLocale::English => value.to_owned(),
+            NumberLocale::German => match Decimal::from_str(value) {
+                Ok(_) => value.replace('.', ","),
+                Err(_) => value.to_owned(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_english() {
+        let locale = NumberLocale::English;
+        assert_eq!(locale.parse("3.5").unwrap(), dec!(3.5));
+        assert_eq!(locale.parse("250,000").unwrap(), dec!(250000));
+        assert_eq!(locale.parse("-1,234.5").unwrap(), dec!(-1234.5));
+        assert_eq!(locale.parse("250,000 €").unwrap(), dec!(250000));
+        assert_eq!(locale.parse("3.5 %").unwrap(), dec!(3.5));
+        assert!(matches!(locale.parse("3,5"), Err(ParseNumberErr::InvalidGrouping)));
+        assert!(matches!(locale.parse("1.2.3"), Err(ParseNumberErr::MultipleDecimalSeparators)));
+        assert!(matches!(locale.parse(" € "), Err(ParseNumberErr::Empty)));
+        assert!(matches!(locale.parse("abc"), Err(ParseNumberErr::InvalidNumber)));
+    }
+
+    #[test]
+    fn parse_german() {
+        let locale = NumberLocale::German;
+        assert_eq!(locale.parse("3,5").unwrap(), dec!(3.5));
+        assert_eq!(locale.parse("250.000").unwrap(), dec!(250000));
+        assert_eq!(locale.parse("250 000 €").unwrap(), dec!(250000));
+        assert_eq!(locale.parse("EUR 1.000,50").unwrap(), dec!(1000.5));
+        assert_eq!(locale.parse("3,5 %").unwrap(), dec!(3.5));
+        assert!(matches!(locale.parse("2.50"), Err(ParseNumberErr::InvalidGrouping)));
+        assert!(matches!(locale.parse("1.0000"), Err(ParseNumberErr::InvalidGrouping)));
+        assert!(matches!(locale.parse(".250"), Err(ParseNumberErr::InvalidGrouping)));
+        assert!(matches!(locale.parse("1,2,3"), Err(ParseNumberErr::MultipleDecimalSeparators)));
+    }
+
+    #[test]
+    fn normalize_and_format() {
+        assert_eq!(NumberLocale::German.normalize("1.234,5"), "1234.5");
+        assert_eq!(NumberLocale::English.normalize("1,234.5"), "1234.5");
+        assert_eq!(NumberLocale::German.normalize("1,2,3"), "1,2,3");
+        assert_eq!(NumberLocale::German.format("1234.5"), "1234,5");
+        assert_eq!(NumberLocale::German.format("abc"), "abc");
+        assert_eq!(NumberLocale::English.format("1234.5"), "1234.5");
+    }
+}
@@ -4,27 +4,17 @@ mod util;
 mod overview;
 mod form;
 mod custom_text_input;
+mod locale;
+mod settings;
 
 use crate::loan_view::{LoanView, LoanViewMessage, LoanViewData, LoanFormData};
 
-use serde::{Serialize, Deserialize};
-
 use iced::{Button, button, Application, Text, Element, Settings, Row, Column, Length, Command, executor};
 use crate::style::Icons;
 use crate::overview::{Overview, OverviewMessage};
 use crate::form::{FormMessage, FormTextInputMessage};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-enum LoanType {
-    Annuity,
-    BuildingSavings,
-}
-
-impl Default for LoanType {
-    fn default() -> Self {
-        LoanType::Annuity
-    }
-}
+use crate::locale::NumberLocale;
+use crate::settings::{AppSettings, SettingsErr};
 
 #[derive(Clone, Debug)]
 enum AppMessage {
@@ -33,7 +23,8 @@ enum AppMessage {
     ShowOverview,
     SelectLoan(usize),
     AddLoan,
-    DeleteLoan
+    DeleteLoan,
+    SettingsSaved(Result<(), SettingsErr>),
 }
 
 #[derive(Default)]
@@ -46,6 +37,7 @@ struct App {
     overview_btn: button::State,
     overview: Overview,
     title: String,
+    locale: NumberLocale,
 }
 
 struct LoanTab {
@@ -76,18 +68,19 @@ impl App {
     fn add_loan(&mut self) {
         let idx = self.loan_tabs.len();
         let loan_name = format!("{} {}", LOAN_DEFAULT_NAME, idx + 1);
-        self.loans.push(Box::new(LoanView::new(loan_name.clone())));
+        self.loans.push(Box::new(LoanView::new(loan_name.clone(), self.locale)));
         self.loan_tabs.push(Box::new(LoanTab::new(loan_name, idx)));
         self.active = Some(idx);
     }
 
-    fn add_loan_with_data(&mut self, data: LoanViewData) {
+    fn add_loan_with_data(&mut self, data: LoanViewData) -> Command<AppMessage> {
         let idx = self.loan_tabs.len();
         self.loan_tabs.push(Box::new(LoanTab::new(data.name.clone(), idx)));
-        let mut loan_view = LoanView::new_with_data(data);
-        loan_view.calc();
+        let mut loan_view = LoanView::new_with_data(data, self.locale);
+        let command = loan_view.calc().map(move |m| AppMessage::LoanViewMessage(idx, m));
         self.loans.push(Box::new(loan_view));
         self.active = Some(idx);
+        command
     }
 
     fn delete_active_load(&mut self) {
@@ -122,6 +115,7 @@ impl Application for App {
     fn new(_flags: Self::Flags) -> (Self, Command<Self::Message>) {
         let mut app = Self::default();
         app.title = String::from("Loan calc");
+        app.locale = AppSettings::load().locale.unwrap_or_else(NumberLocale::from_env);
         app.add_loan();
         (app, Command::none())
     }
@@ -139,7 +133,7 @@ impl Application for App {
                     }
                 }
                 if let Some(calc) = self.loans.get_mut(idx) {
-                    calc.update(msg);
+                    return calc.update(msg).map(move |m| AppMessage::LoanViewMessage(idx, m));
                 }
             }
             AppMessage::SelectLoan(idx) => {
@@ -157,10 +151,18 @@ impl Application for App {
                         self.loans.clear();
                         self.loan_tabs.clear();
                         self.title = format!("Loan calc - {}", loaded.file);
-                        for loan in loaded.data {
-                            self.add_loan_with_data(loan);
-                        }
+                        let commands: Vec<_> = loaded.data.into_iter().map(|loan| self.add_loan_with_data(loan)).collect();
+                        return Command::batch(commands);
+                    }
+                }else if let OverviewMessage::ChangeLocale(locale) = &msg {
+                    self.locale = *locale;
+                    for loan in self.loans.iter_mut() {
+                        loan.set_locale(*locale);
                     }
+                    let settings = AppSettings {
+                        locale: Some(*locale),
+                    };
+                    return Command::perform(settings.save(), AppMessage::SettingsSaved);
                 }else {
                     return self.overview.update(msg).map(|m| AppMessage::OverviewMessage(m));
                 }
@@ -168,6 +170,7 @@ impl Application for App {
             AppMessage::DeleteLoan => {
                 self.delete_active_load();
             }
+            AppMessage::SettingsSaved(_) => {}
         }
         Command::none()
     }
@@ -210,7 +213,7 @@ impl Application for App {
                 col = col.push(active.view().map(move |m| AppMessage::LoanViewMessage(idx, m)));
             }
         }else {
-            col = col.push(self.overview.view(&self.loans).map(|m| AppMessage::OverviewMessage(m)));
+            col = col.push(self.overview.view(&self.loans, self.locale).map(|m| AppMessage::OverviewMessage(m)));
         }
         col.into()
     }
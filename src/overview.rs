@@ -1,10 +1,15 @@
 use iced::{Element, Text, button, Command, Row};
 use crate::loan_view::{LoanView, LoanViewData};
+use crate::locale::NumberLocale;
+use crate::style::ButtonStyle;
+use loan_calc::calc::CalcResultOverview;
+use chrono::Datelike;
 use rust_decimal::Decimal;
 use iced_native::{Column, Button};
 use nfd::{Response};
 use std::{
     result::Result,
+    collections::BTreeMap,
 };
 
 const FILE_EXT: &'static str = "lc";
@@ -13,6 +18,8 @@ const FILE_EXT: &'static str = "lc";
 pub struct Overview {
     save_btn: button::State,
     load_btn: button::State,
+    english_locale_btn: button::State,
+    german_locale_btn: button::State,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +34,7 @@ pub enum OverviewMessage {
     SaveDlgResult(Result<(), OverviewErr>),
     OpenLoadDlg,
     LoadDlgResult(Result<LoadResult, OverviewErr>),
+    ChangeLocale(NumberLocale),
 }
 
 #[derive(Debug, Clone)]
@@ -41,29 +49,76 @@ pub enum OverviewErr {
 }
 
 impl Overview {
-    pub fn view(&mut self, loans: &Vec<Box<LoanView>>) -> Element<OverviewMessage> {
+    pub fn view(&mut self, loans: &Vec<Box<LoanView>>, locale: NumberLocale) -> Element<OverviewMessage> {
         let mut monthly_rate = Decimal::new(0, 2);
+        let mut max_monthly_rate = Decimal::new(0, 2);
         let mut remaining = Decimal::new(0, 2);
         let mut paid_interest = Decimal::new(0, 2);
         let mut cleared_amount = Decimal::new(0, 2);
+        let mut grant = Decimal::new(0, 2);
+        let mut cost = Decimal::new(0, 2);
+        let results = loans.iter()
+            .filter_map(|l| l.result.as_ref())
+            .collect::<Vec<&CalcResultOverview>>();
+        // Loans are combined by calendar month if all of them have due dates, by counter otherwise.
+        let by_date = results.iter().all(|r| r.months.iter().all(|m| m.date.is_some()));
+        // A payment covering several months, e.g. of a quarterly loan, is spread over them.
+        let mut payments = BTreeMap::new();
+        for result in results.iter() {
+            let mut previous = 0;
+            for month in result.months.iter() {
+                let key = match month.date {
+                    Some(date) if by_date => date.year() * 12 + date.month0() as i32,
+                    _ => month.month,
+                };
+                let months = (month.month - previous).max(1);
+                previous = month.month;
+                for key in key - months + 1..=key {
+                    *payments.entry(key).or_insert(Decimal::new(0, 2)) += month.payment / Decimal::from(months);
+                }
+            }
+        }
+        if let Some(rate) = payments.values().next() {
+            monthly_rate = *rate;
+        }
+        for rate in payments.values() {
+            max_monthly_rate = max_monthly_rate.max(*rate);
+        }
         for loan in loans {
             if let Some(res) = &loan.result {
-                monthly_rate += res.monthly_rate;
                 remaining += res.overall.remaining;
                 paid_interest += res.overall.paid_interest;
                 cleared_amount += res.overall.cleared_amount;
+                grant += res.overall.grant;
+                cost += res.cost();
             }
         }
-        Column::new()
+        let mut ranking = loans.iter()
+            .filter_map(|l| l.result.as_ref().and_then(|r| r.effective_rate).map(|rate| (l.data.name.clone(), rate)))
+            .collect::<Vec<(String, Decimal)>>();
+        ranking.sort_by(|a, b| a.1.cmp(&b.1));
+        let ranking = ranking.iter().enumerate()
+            .map(|(idx, (name, rate))| format!("{}. {}: {} %", idx + 1, name, rate.round_dp(2)))
+            .collect::<Vec<String>>();
+
+        let mut col = Column::new()
             .spacing(20)
             .padding(20)
             .push(Text::new(
-                format!("Monthly rate: {}\nPaid interest: {}\nRemaining: {}\nCleared: {}",
+                format!("Monthly rate: {}\nHighest monthly rate: {}\nPaid interest: {}\nRemaining: {}\nCleared: {}",
                           monthly_rate.round_dp(2),
+                          max_monthly_rate.round_dp(2),
                           paid_interest.round_dp(2),
                           remaining.round_dp(2),
                           cleared_amount.round_dp(2),
-        ))).push(
+        )));
+        if grant > Decimal::new(0, 2) {
+            col = col.push(Text::new(format!("Repayment grants: {}\nCost after grants: {}", grant.round_dp(2), cost.round_dp(2))));
+        }
+        if !ranking.is_empty() {
+            col = col.push(Text::new(format!("Effective rates:\n{}", ranking.join("\n"))));
+        }
+        col.push(
             Row::new()
                 .spacing(4)
                 .push(
@@ -72,6 +127,14 @@ impl Overview {
                 ).push(
                 Button::new(&mut self.load_btn, Text::new("Load"))
                     .on_press(OverviewMessage::OpenLoadDlg)
+            ).push(
+                Button::new(&mut self.english_locale_btn, Text::new("1,234.5"))
+                    .on_press(OverviewMessage::ChangeLocale(NumberLocale::English))
+                    .style(ButtonStyle { active: locale == NumberLocale::English })
+            ).push(
+                Button::new(&mut self.german_locale_btn, Text::new("1.234,5"))
+                    .on_press(OverviewMessage::ChangeLocale(NumberLocale::German))
+                    .style(ButtonStyle { active: locale == NumberLocale::German })
             )
         ).into()
     }
@@ -92,7 +155,7 @@ impl Overview {
         match nfd::open_file_dialog(Some(FILE_EXT), None).map_err(|_| OverviewErr::ShowDlgFailed)? {
             Response::Okay(path) => {
                 let content = std::fs::read(&path).map_err(|_| OverviewErr::LoadFileFailed)?;
-                let data = serde_json::from_slice::<Vec<LoanViewData>>(&content).map_err(|_| OverviewErr::DeserializeFailed)?;
+                let data = LoanViewData::from_file(&content).map_err(|_| OverviewErr::DeserializeFailed)?;
                 Ok(LoadResult {
                     data,
                     file: path
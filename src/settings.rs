@@ -0,0 +1,44 @@
+use crate::locale::NumberLocale;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Settings kept between runs in `loan_calc/settings.json` of the user's config directory.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AppSettings {
+    /// Chosen number locale, taken from the environment if not chosen yet.
+    #[serde(default)]
+    pub locale: Option<NumberLocale>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsErr {
+    NoConfigDir,
+    SerializeFailed,
+    WriteFileFailed,
+}
+
+impl AppSettings {
+    fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("loan_calc").join("settings.json"))
+    }
+
+    /// Loads the saved settings, the defaults if there are none or they cannot be read.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub async fn save(self) -> Result<(), SettingsErr> {
+        let path = Self::path().ok_or(SettingsErr::NoConfigDir)?;
+        let json = serde_json::to_string(&self).map_err(|_| SettingsErr::SerializeFailed)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|_| SettingsErr::WriteFileFailed)?;
+        }
+        std::fs::write(&path, json).map_err(|_| SettingsErr::WriteFileFailed)
+    }
+}
@@ -1,5 +1,5 @@
 use iced::widget::button::Style;
-use iced::{Color, button, Text, Background};
+use iced::{Color, button, text_input, Text, Background};
 use crate::util::icon;
 
 pub struct Icons {}
@@ -25,14 +25,14 @@ impl Icons {
     // }
 }
 
-struct Colors {}
+pub struct Colors {}
 impl Colors {
     fn blue() -> Color {
         Color::from_rgba8(0,190, 255, 0.7)
     }
-    // fn red() -> Color {
-    //     Color::from_rgba8(190,0, 0, 0.7)
-    // }
+    pub fn red() -> Color {
+        Color::from_rgba8(190,0, 0, 0.7)
+    }
     // fn grey() -> Color {
     //     Color::from_rgba8(190,190, 190, 0.7)
     // }
@@ -92,4 +92,35 @@ impl button::StyleSheet for ListButtonStyle {
             ..button::Style::default()
         }
     }
-}
\ No newline at end of file
+}
+
+pub struct ErrorTextInputStyle {}
+impl text_input::StyleSheet for ErrorTextInputStyle {
+    fn active(&self) -> text_input::Style {
+        text_input::Style {
+            background: Background::Color(Color::WHITE),
+            border_radius: 5,
+            border_width: 1,
+            border_color: Colors::red(),
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        text_input::Style {
+            border_width: 2,
+            ..self.active()
+        }
+    }
+
+    fn placeholder_color(&self) -> Color {
+        Color::from_rgb(0.7, 0.7, 0.7)
+    }
+
+    fn value_color(&self) -> Color {
+        Color::from_rgb(0.3, 0.3, 0.3)
+    }
+
+    fn selection_color(&self) -> Color {
+        Color::from_rgb(0.8, 0.8, 1.0)
+    }
+}